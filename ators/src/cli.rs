@@ -1,9 +1,10 @@
-use crate::context::Loc;
+use atorsl::data::Loc;
 use clap::{
     crate_authors, crate_description, crate_name, crate_version, value_parser, Arg, ArgAction,
     Command, ValueHint,
 };
-use std::{fmt, path::PathBuf};
+use std::{fmt, net::SocketAddr, num::NonZeroUsize, path::PathBuf};
+use uuid::Uuid;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Opt {
@@ -19,8 +20,31 @@ pub enum Opt {
     FullPath,
     PrefixAddr,
     Uuid,
+    DwoDir,
+    DebugDir,
+    Jobs,
+    Format,
+    Column,
+    CrashReport,
+    UuidLookup,
+    Search,
+    CacheDir,
+    Socket,
+    Http,
+    MaxMemory,
+    Extract,
+    Filter,
+    LookupSymbol,
+    LookupLine,
+    Images,
 }
 
+/// The name of the subcommand writing a Breakpad symbol file.
+pub const DUMP_SYMS: &str = "dump-syms";
+
+/// The name of the subcommand serving symbolication requests.
+pub const SERVE: &str = "serve";
+
 impl fmt::Display for Opt {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         fmt::Debug::fmt(self, f)
@@ -45,21 +69,30 @@ pub fn build() -> Command {
         .version(crate_version!())
         .before_help(TITLE)
         .arg_required_else_help(true)
-        .override_usage("[OPTIONS] -o <binary|dSYM> <-l <load-address>|-s <slide>|--offset> <-f <address-input-file>|-- address...>")
+        .subcommand_negates_reqs(true)
+        .args_conflicts_with_subcommands(true)
+        .override_usage(
+            "[OPTIONS] <-o <binary|dSYM>|--uuid-lookup <UUID> --search <directory>...> <-l <load-address>|-s <slide>|--offset> [-f <address-input-file>|-- address...]\n       \
+            [OPTIONS] <-o <binary|dSYM>@<load-address>...|--images <file>> [-f <address-input-file>|-- address...]")
         .args([
             Arg::new(Opt::Object)
                 .short('o')
                 .help_heading("Arguments")
-                .help("The path to a binary image or dSYM in which to look up symbols.")
-                .required(true)
+                .help("The path to a binary image, dSYM or Breakpad symbol file in which to look up symbols.")
+                .required_unless_present_any([Opt::UuidLookup, Opt::CrashReport, Opt::Images])
+                .action(ArgAction::Append)
                 .value_hint(ValueHint::FilePath)
                 .value_name("binary|dSYM")
-                .value_parser(value_parser!(PathBuf)),
+                .value_parser(value_parser!(PathBuf))
+                .long_help(
+                    "The path to a binary image, dSYM or Breakpad symbol file in which to look up\n\
+                    symbols.  To symbolicate addresses across several binary images, repeat it\n\
+                    with the load address of each after an @, as -o MyApp@0x100000000, and\n\
+                    each address is looked up in the binary image whose text it falls in."),
             Arg::new(Opt::LoadAddr)
                 .short('l')
                 .help_heading("Arguments")
                 .help("The load address of the binary image.")
-                .required_unless_present_any([Opt::Uuid, Opt::SlideAddr, Opt::Offset])
                 .value_name("load-address")
                 .value_parser(|addr: &str| addr.parse().map(Loc::Load))
                 .long_help(
@@ -72,7 +105,6 @@ pub fn build() -> Command {
                 .short('s')
                 .help_heading("Arguments")
                 .help("The slide value of the binary image.")
-                .required_unless_present_any([Opt::Uuid, Opt::LoadAddr, Opt::Offset])
                 .value_name("slide")
                 .value_parser(|addr: &str| addr.parse().map(Loc::Slide))
                 .long_help(
@@ -85,7 +117,6 @@ pub fn build() -> Command {
                 .long("offset")
                 .help_heading("Arguments")
                 .help("Treat all given addresses as offsets into the binary.")
-                .required_unless_present_any([Opt::Uuid, Opt::LoadAddr, Opt::SlideAddr])
                 .action(ArgAction::SetTrue)
                 .long_help(
                     "Treat all given addresses as offsets into the binary. Only one of the\n\
@@ -94,24 +125,96 @@ pub fn build() -> Command {
                 .short('f')
                 .help_heading("Arguments")
                 .help("Input file with white-separated numeric addresses.")
-                .required_unless_present_any([Opt::Uuid, Opt::Addr])
                 .value_hint(ValueHint::FilePath)
                 .value_name("address-input-file")
                 .value_parser(value_parser!(PathBuf))
                 .long_help(
                     "Use this argument to specify the path of an input file containing\n\
-                    whitespace-separated numeric addresses."),
+                    whitespace-separated numeric addresses.  When neither an input file nor\n\
+                    addresses are given, addresses are read from the standard input a line at a\n\
+                    time, and the symbols of each line are printed as soon as it's read."),
             Arg::new(Opt::Addr)
                 .last(true)
                 .help_heading("Arguments")
                 .help("\tA list of input addresses at the end of the argument list.")
-                .required_unless_present_any([Opt::Uuid, Opt::AddrFile])
                 .action(ArgAction::Append)
                 .num_args(1..)
                 .value_name("address")
                 .value_parser(str::parse::<atorsl::data::Addr>),
         ])
         .args([
+            Arg::new(Opt::Filter)
+                .long("filter")
+                .help("Symbolicate the frames of the binary image in backtrace text, printing the text with their symbols")
+                .conflicts_with_all([Opt::Addr, Opt::Uuid, Opt::CrashReport, Opt::Extract])
+                .action(ArgAction::SetTrue)
+                .long_help(
+                    "Read backtrace text from the input file, or from the standard input a line at\n\
+                    a time, and print it with the frames of the binary image symbolicated in\n\
+                    place, and their inlined symbols as frames of their own when -i is given.\n\
+                    Frames of Apple crash reports, backtrace_symbols, Rust panic backtraces and\n\
+                    Android tombstones are recognized, and belong to the binary image when they\n\
+                    name it, or, when they name no image, when their address is in its debug\n\
+                    info.  Addresses are taken as given with -l, -s or --offset, but for frames\n\
+                    of crash reports with the load address of their image, and of tombstones,\n\
+                    relative to theirs.  Any other line is printed as is."),
+            Arg::new(Opt::Images)
+                .long("images")
+                .help("A file listing binary images and their load address, to symbolicate addresses across")
+                .conflicts_with_all([Opt::LoadAddr, Opt::SlideAddr, Opt::Offset, Opt::Uuid, Opt::CrashReport, Opt::Filter, Opt::LookupSymbol, Opt::LookupLine])
+                .value_hint(ValueHint::FilePath)
+                .value_name("file")
+                .value_parser(value_parser!(PathBuf))
+                .long_help(
+                    "A file listing binary images and their load address, a path@load-address per\n\
+                    line, as given with -o, to symbolicate addresses across, along with the ones\n\
+                    given with -o.  Each address is looked up in the binary image whose text it\n\
+                    falls in, and reported as in no binary image otherwise.  Empty lines and\n\
+                    lines starting with # are skipped."),
+            Arg::new(Opt::LookupSymbol)
+                .long("lookup-symbol")
+                .help("Print the address ranges of the functions with this name, or matching this regex")
+                .conflicts_with_all([Opt::Addr, Opt::AddrFile, Opt::Uuid, Opt::CrashReport, Opt::Filter, Opt::Extract])
+                .value_name("name|regex")
+                .value_parser(value_parser!(String))
+                .allow_hyphen_values(true)
+                .long_help(
+                    "Print the address ranges of the code of the functions with this name, or\n\
+                    whose name this regular expression matches in full, every inlined copy\n\
+                    included, along with the function each copy was inlined into.  Names are\n\
+                    matched both demangled and as found in the debug info or symbol table.\n\
+                    Functions are looked up in the debug info, then in the symbol table, and\n\
+                    addresses are printed as given with -l, -s or --offset."),
+            Arg::new(Opt::LookupLine)
+                .long("lookup-line")
+                .help("Print the address ranges of the code of a source line, and the functions of each")
+                .conflicts_with_all([Opt::Addr, Opt::AddrFile, Opt::Uuid, Opt::CrashReport, Opt::Filter, Opt::Extract, Opt::LookupSymbol])
+                .value_hint(ValueHint::FilePath)
+                .value_name("file:line")
+                .value_parser(|source_line: &str| {
+                    source_line
+                        .rsplit_once(':')
+                        .and_then(|(file, line)| Some((PathBuf::from(file), line.parse::<u64>().ok()?)))
+                        .ok_or("expected <file>:<line>")
+                })
+                .long_help(
+                    "Print the address ranges of the code the debug info attributes to a line of\n\
+                    a source file, each along with the function it belongs to and the functions\n\
+                    inlined into it down to the one of the line, innermost first.  The file\n\
+                    matches source files whose path ends with it, as Foo.swift or\n\
+                    Sources/App/Foo.swift.  Addresses are printed as given with -l, -s or\n\
+                    --offset."),
+            Arg::new(Opt::Extract)
+                .long("extract")
+                .help("Extract the address of each line of input from free-form text, as a backtrace")
+                .action(ArgAction::SetTrue)
+                .long_help(
+                    "Extract the address of each line of the input file, or of the standard\n\
+                    input, from free-form text, as the first 0x-prefixed hex number in the line,\n\
+                    such as the address of a frame in a backtrace.  Lines without one are\n\
+                    skipped.  Without it, every whitespace-separated word of a line must be an\n\
+                    address, anything after a # is a comment, and words that aren't addresses\n\
+                    are reported by line."),
             Arg::new(Opt::Arch)
                 .long("arch")
                 .help("The architecure of a binary image in which to look up symbols")
@@ -127,16 +230,74 @@ pub fn build() -> Command {
                     (such as i386 or arm) and pass in a corresponding symbol-rich Mach-O binary\n\
                     image file with a binary image of the corresponding architecture (such as a\n\
                     Universal Binary)."),
+            dwo_dir_arg(),
+            debug_dir_arg(),
+            cache_dir_arg(),
+            Arg::new(Opt::Format)
+                .long("format")
+                .help("The format in which to print symbols")
+                .value_name("format")
+                .value_parser(["text", "json", "ndjson"])
+                .default_value("text")
+                .long_help(
+                    "The format in which to print symbols.  \"text\" prints them as atos does,\n\
+                    \"json\" prints an array with an object for each address, once every address\n\
+                    is symbolicated, and \"ndjson\" prints each object in its own line as soon as\n\
+                    its address is symbolicated.  Each object has the input address, the address\n\
+                    in the binary image, the image name, and its frames, innermost first, with\n\
+                    their symbol, mangled name, file, line, column, offset, whether they were\n\
+                    inlined, and whether they were found in the DWARF or the symbol table.  With\n\
+                    --uuid, each object has the UUID, architecture and path of a binary image."),
+            Arg::new(Opt::Jobs)
+                .short('j')
+                .long("jobs")
+                .help("Symbolicate addresses across this many threads")
+                .value_name("threads")
+                .value_parser(value_parser!(NonZeroUsize))
+                .default_value("1")
+                .long_help(
+                    "Symbolicate addresses across this many threads.  Addresses are still printed\n\
+                    in the order they were given."),
+            Arg::new(Opt::CrashReport)
+                .long("crash-report")
+                .help("Symbolicate the frames of an Apple crash report")
+                .conflicts_with_all([Opt::LoadAddr, Opt::SlideAddr, Opt::Offset, Opt::AddrFile, Opt::Addr, Opt::Uuid])
+                .value_hint(ValueHint::FilePath)
+                .value_name("file")
+                .value_parser(value_parser!(PathBuf))
+                .long_help(
+                    "Symbolicate the frames of an Apple crash report, either a .crash or an .ips,\n\
+                    and print it with each frame replaced by its symbol, and its inlined symbols\n\
+                    when -i is given.  Binary images of the report are matched by UUID to the\n\
+                    binary images and dSYMs given with -o, which may be repeated, or found in the\n\
+                    directories given with --search, and symbolicated at the load address listed\n\
+                    in the report.  Frames of binary images that don't match any are left\n\
+                    untouched."),
+            Arg::new(Opt::UuidLookup)
+                .long("uuid-lookup")
+                .help("Look up symbols in the binary image with this UUID")
+                .conflicts_with(Opt::CrashReport)
+                .value_name("UUID")
+                .value_parser(Uuid::parse_str)
+                .long_help(
+                    "Look up symbols in the binary image with this UUID, found in the directories\n\
+                    given with --search, or among the DWARF files of the dSYM given with -o.  The\n\
+                    architecture with this UUID is selected in universal binaries, and a dSYM is\n\
+                    preferred over a binary image without DWARF.  A -o that isn't a dSYM can't be\n\
+                    selected from, and is rejected."),
+            search_arg(),
             Arg::new(Opt::Uuid)
                 .long("uuid")
-                .help("Print the UUID of the binary image and exit.")
-                .requires(Opt::Object)
+                .help("Print the UUID of each architecture of the binary images and exit.")
                 .conflicts_with_all([Opt::LoadAddr, Opt::SlideAddr, Opt::Offset, Opt::AddrFile, Opt::Addr])
                 .action(ArgAction::SetTrue)
                 .long_help(
-                    "Print the UUID of the binary image and exit.  This will produce the same\n\
-                    output as the symbols program when passing the -uuid argument along with\n\
-                    the path of the object."),
+                    "Print the UUID of each architecture of the binary images given with -o, which\n\
+                    may be repeated, and of each DWARF file of the dSYMs, and exit.  Each is\n\
+                    printed in a line of its own with its architecture and path, as the symbols\n\
+                    program does when passing the -uuid argument, or only the ones of the\n\
+                    architecture given with --arch.  The build ID is printed instead for ELF\n\
+                    objects."),
             Arg::new(Opt::Delimiter)
                 .short('d')
                 .help("Delimiter when outputting inline frames.")
@@ -152,11 +313,88 @@ pub fn build() -> Command {
                 .long("fullPath")
                 .help("Print the full path of the source files")
                 .action(ArgAction::SetTrue),
+            Arg::new(Opt::Column)
+                .long("column")
+                .help("Print the column of the source location after its line")
+                .action(ArgAction::SetTrue)
+                .long_help(
+                    "Print the column of the source location after its line, as in\n\
+                    \"file:line:column\".  The column is left out when the debug info doesn't\n\
+                    have it."),
             Arg::new(Opt::PrefixAddr)
                 .long("prefixAddr")
                 .hide(true)
                 .action(ArgAction::SetTrue),
         ])
+        .subcommand(
+            Command::new(DUMP_SYMS)
+                .about("Write the debug info of a binary image as a Breakpad symbol file")
+                .long_about(
+                    "Write the debug info of a binary image as a Breakpad symbol file to the\n\
+                    standard output, with a FUNC record for each function in its DWARF, along\n\
+                    with their line records and an INLINE record for each function inlined into\n\
+                    them, and a PUBLIC record for each symbol of its symbol table outside of\n\
+                    them.  The symbol file can be given to -o in place of the binary image.  Its\n\
+                    INFO BASE_ADDRESS record, which only ators reads, lets addresses be\n\
+                    symbolicated as built; other symbol files only take a load address or\n\
+                    --offset.  Functions starting below the base address are reported and left\n\
+                    out.")
+                .args([
+                    Arg::new(Opt::Object)
+                        .help("The path to a binary image or dSYM to dump")
+                        .required(true)
+                        .value_hint(ValueHint::FilePath)
+                        .value_name("binary|dSYM")
+                        .value_parser(value_parser!(PathBuf)),
+                    Arg::new(Opt::Arch)
+                        .long("arch")
+                        .help("The architecure of a binary image to dump")
+                        .value_name("architecture")
+                        .value_parser(value_parser!(String)),
+                ]),
+        )
+        .subcommand(
+            Command::new(SERVE)
+                .about("Serve symbolication requests over a Unix socket or local HTTP")
+                .long_about(
+                    "Serve symbolication requests over a Unix socket or local HTTP, keeping the\n\
+                    binary images symbolicated against loaded for the requests after.  Each\n\
+                    request is a JSON object with the \"image\" path to a binary image, dSYM or\n\
+                    Breakpad symbol file, or its \"uuid\" to find it in the directories given\n\
+                    with --search, along with its \"arch\", its \"load_address\" or \"slide\",\n\
+                    or neither for offsets, the \"addresses\" to symbolicate, and whether to\n\
+                    expand \"inline\" frames, print the \"full_path\" of source files and their\n\
+                    \"column\".  Each response is a JSON object with the \"symbolications\" of\n\
+                    the addresses, as printed with --format json, or the \"error\" of the\n\
+                    request.  Over a Unix socket, requests and responses are a JSON object per\n\
+                    line, over HTTP, the body of a POST and of its response.  Connections that\n\
+                    send nothing or read nothing for a minute are closed.")
+                .args([
+                    Arg::new(Opt::Socket)
+                        .long("socket")
+                        .help("The path of a Unix socket on which to serve requests")
+                        .required_unless_present(Opt::Http)
+                        .conflicts_with(Opt::Http)
+                        .value_hint(ValueHint::FilePath)
+                        .value_name("path")
+                        .value_parser(value_parser!(PathBuf)),
+                    Arg::new(Opt::Http)
+                        .long("http")
+                        .help("The local address on which to serve requests over HTTP")
+                        .value_name("address:port")
+                        .value_parser(value_parser!(SocketAddr)),
+                    Arg::new(Opt::MaxMemory)
+                        .long("max-memory")
+                        .help("The memory, in MiB, above which the least recently used binary images are unloaded")
+                        .value_name("MiB")
+                        .value_parser(value_parser!(usize))
+                        .default_value("1024"),
+                    search_arg(),
+                    dwo_dir_arg(),
+                    debug_dir_arg(),
+                    cache_dir_arg(),
+                ]),
+        )
         .after_long_help(
             "\t\t\t\t- - -\n\n\
             A stripped, optimized version of Sketch was built as an x86_64 position-independent\n\
@@ -176,6 +414,76 @@ pub fn build() -> Command {
             -[SKTGraphic drawHandleInView:atPoint:] (in Sketch) (SKTGraphic.m:490)")
 }
 
+/// The `--search` argument, shared by the top-level command and the `serve` subcommand.
+fn search_arg() -> Arg {
+    Arg::new(Opt::Search)
+        .long("search")
+        .help("A directory in which to look for binary images and dSYMs by UUID")
+        .action(ArgAction::Append)
+        .value_hint(ValueHint::DirPath)
+        .value_name("directory")
+        .value_parser(value_parser!(PathBuf))
+        .long_help(
+            "A directory in which to look for binary images and dSYMs by UUID, for\n\
+            --uuid-lookup, --crash-report and the \"uuid\" of the requests to serve.\n\
+            Directories are searched recursively, for .dSYM bundles, the dSYMs of\n\
+            .xcarchive bundles, and loose Mach-O binary images.  May be repeated.",
+        )
+}
+
+/// The `--dwo-dir` argument, shared by the top-level command and the `serve` subcommand.
+fn dwo_dir_arg() -> Arg {
+    Arg::new(Opt::DwoDir)
+        .long("dwo-dir")
+        .help("A directory in which to look for split DWARF .dwo and .dwp files")
+        .action(ArgAction::Append)
+        .value_hint(ValueHint::DirPath)
+        .value_name("directory")
+        .value_parser(value_parser!(PathBuf))
+        .long_help(
+            "A directory in which to look for the split DWARF of a binary image built\n\
+            with -gsplit-dwarf.  Each .dwo file is first looked up at the path recorded\n\
+            in the binary image, and a .dwp package next to the binary image, then by\n\
+            file name in each given directory.  May be repeated.",
+        )
+}
+
+/// The `--debug-dir` argument, shared by the top-level command and the `serve` subcommand.
+fn debug_dir_arg() -> Arg {
+    Arg::new(Opt::DebugDir)
+        .long("debug-dir")
+        .help("A directory in which to look for the separate debug files of ELF objects, or the PDBs of PE images")
+        .action(ArgAction::Append)
+        .value_hint(ValueHint::DirPath)
+        .value_name("directory")
+        .value_parser(value_parser!(PathBuf))
+        .long_help(
+            "A directory in which to look for the separate debug file of a stripped ELF\n\
+            object, as .build-id/xx/yyyy.debug by its build ID, and by the file name in\n\
+            its .gnu_debuglink under the directory of the object.  The debuglink is also\n\
+            looked up next to the object and in a .debug directory next to it, and\n\
+            /usr/lib/debug is searched after the given directories.  The PDB of a PE\n\
+            image is looked up by the path in its CodeView record, then by its file name\n\
+            next to the image and in the given directories.  May be repeated.")
+}
+
+/// The `--cache-dir` argument, shared by the top-level command and the `serve` subcommand.
+fn cache_dir_arg() -> Arg {
+    Arg::new(Opt::CacheDir)
+        .long("cache-dir")
+        .help("A directory in which to keep symbol caches of binary images")
+        .value_hint(ValueHint::DirPath)
+        .value_name("directory")
+        .value_parser(value_parser!(PathBuf))
+        .long_help(
+            "A directory in which to keep symbol caches of binary images, to symbolicate\n\
+            against without parsing their DWARF again.  The cache of a binary image is\n\
+            named after its UUID, or build ID, and architecture, and is written on the\n\
+            first run against the binary image and read on every run after it.  Caches\n\
+            written by another version of ators are written again.",
+        )
+}
+
 // The invisible character prevents Rust from triming first line's starting whitespaces.
 #[allow(clippy::invisible_characters)]
 const TITLE: &str = r#"
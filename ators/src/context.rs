@@ -1,61 +1,99 @@
-use crate::{cli, Addr};
+use crate::{cli, serve::Request, uuid_index};
 use anyhow::{Context as _, Result};
-use atorsl::ext::object::Architecture as _;
-use itertools::Either;
-use object::Architecture;
+use atorsl::{
+    data::{Addr, Arch, Loc},
+    debug_file, AddrInput, Dsym, UuidIndex,
+};
 use std::{
     fs,
+    num::NonZeroUsize,
     path::{Path, PathBuf},
 };
+use uuid::Uuid;
 
-/// The location address of the binary image containing symbol addresses.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum Loc {
-    /// The load address of the binary image.
-    ///
-    /// Load addresses for binary images can be found in the Binary Images: section at the
-    /// bottom of the crash, sample, leaks, and malloc_history reports.
-    Load(Addr),
+pub enum Mode {
+    Symbolicate,
+    PrintUuid,
+    SymbolicateCrashReport,
+    Filter,
+    LookupSymbol,
+    LookupLine,
+    SymbolicateImages,
+}
 
-    /// The slide value of the binary image.
-    ///
-    /// This is the difference between the load address of a binary image, and the address at
-    /// which the binary image was built.
-    ///
-    /// This slide value is subtracted from the input addresses.
-    Slide(Addr),
+/// The format in which symbols are printed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Format {
+    /// atos-style text.
+    Text,
 
-    /// Treat all given addresses as offsets into the binary.
-    Offset,
+    /// A JSON array of an object per address.
+    Json,
+
+    /// A JSON object per address and line.
+    Ndjson,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum Mode {
-    Symbolicate,
-    PrintUuid,
+/// Where the addresses to symbolicate are read from.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Addrs<'ctx> {
+    /// The addresses given at the end of the argument list.
+    List(Vec<Addr>),
+
+    /// An input file with white-separated numeric addresses.
+    File(&'ctx Path),
+
+    /// The standard input, a line of white-separated numeric addresses at a time, as `atos`
+    /// reads them when given none.
+    Stdin,
 }
 
 /// The program's context, defines its behavior.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Context<'ctx> {
-    /// Whether we want to symbolicate or print uuid of the binary
+    /// Whether we want to symbolicate, print uuid of the binary, symbolicate a crash report or
+    /// the frames of backtrace text, look up the code of functions or source lines, or
+    /// symbolicate across several binary images
     pub mode: Mode,
 
     /// The full path to either a binary image, eg. the DWARF file, or a .dSYM.
     ///
-    /// When provided with a .dSYM, the _first file in alphabetical descending order_ in
-    /// `Contents/Resources/DWARF` is loaded. This emulates `atos` behavior.
+    /// When provided with a .dSYM, the file in `Contents/Resources/DWARF` with the requested
+    /// architecture is loaded, or the one named after the .dSYM when there are several.
     pub obj_path: PathBuf,
 
+    /// The paths to every binary image or .dSYM given, as given.
+    pub obj_paths: Vec<PathBuf>,
+
+    /// The binary images given with their load address, with the paths of .dSYMs resolved to
+    /// their DWARF file, for each address to be symbolicated in the one whose text it's in.
+    pub images: Vec<(PathBuf, Addr)>,
+
+    /// The path to the crash report to symbolicate.
+    pub crash_report: Option<&'ctx Path>,
+
+    /// The name of the functions to look up the code of, or a regex matching it.
+    pub lookup_symbol: Option<&'ctx str>,
+
+    /// The source file, or the last components of its path, and the line to look up the
+    /// code of.
+    pub lookup_line: Option<&'ctx (PathBuf, u64)>,
+
+    /// Directories in which to look for binary images and .dSYMs by UUID.
+    pub search_dirs: Vec<PathBuf>,
+
     /// The location address of the binary image containing the addresses to symbolicate.
     pub base_addr: &'ctx Loc,
 
-    /// Either a list of addresses to symbolicate or an input file with white-separated
-    /// numeric addresses.
-    pub addrs: Either<Vec<Addr>, &'ctx Path>,
+    /// Where the addresses to symbolicate are read from.
+    pub addrs: Addrs<'ctx>,
+
+    /// How addresses are found in the lines of an input file or the standard input.
+    pub addr_input: AddrInput,
 
     /// The particular architecure of a binary image file in which to look up symbols.
-    pub arch: Option<Architecture>,
+    pub arch: Option<Arch>,
 
     /// Whether to expand inlined symbols.
     pub include_inlined: bool,
@@ -68,52 +106,154 @@ pub struct Context<'ctx> {
 
     /// Prefix all symbols with their respective address
     pub prefix_addrs: bool,
+
+    /// Print the column of source locations after their line
+    pub show_column: bool,
+
+    /// Directories in which to look for the .dwo and .dwp files of split DWARF.
+    pub dwo_dirs: Vec<PathBuf>,
+
+    /// Directories in which to look for the separate debug files of stripped ELF objects.
+    pub debug_dirs: Vec<PathBuf>,
+
+    /// The directory in which to keep the symbol caches of binary images.
+    pub cache_dir: Option<&'ctx Path>,
+
+    /// The number of threads to symbolicate addresses across.
+    pub jobs: NonZeroUsize,
+
+    /// The format in which to print symbols.
+    pub format: Format,
 }
 
 impl<'a> Context<'a> {
     pub fn from_args(args: &'a clap::ArgMatches) -> Result<Self> {
+        let search_dirs = search_dirs(args);
+
+        let uuid = args.get_one::<Uuid>(&cli::Opt::UuidLookup.to_string());
+        let found_image = uuid
+            .filter(|_| !search_dirs.is_empty())
+            .map(|uuid| {
+                uuid_index(&search_dirs)?
+                    .find(uuid.as_bytes())
+                    .cloned()
+                    .with_context(|| format!("No binary image with UUID {uuid:X}"))
+            })
+            .transpose()?;
+
+        let arch = match &found_image {
+            Some(image) => Some(image.arch),
+            None => args
+                .get_one(&cli::Opt::Arch.to_string())
+                .map(String::as_str)
+                .map(Arch::from_name),
+        };
+
+        let images = images(args, arch)?;
+
+        let mode = if args.get_flag(&cli::Opt::Uuid.to_string()) {
+            Mode::PrintUuid
+        } else if args.contains_id(&cli::Opt::CrashReport.to_string()) {
+            Mode::SymbolicateCrashReport
+        } else if args.get_flag(&cli::Opt::Filter.to_string()) {
+            Mode::Filter
+        } else if args.contains_id(&cli::Opt::LookupSymbol.to_string()) {
+            Mode::LookupSymbol
+        } else if args.contains_id(&cli::Opt::LookupLine.to_string()) {
+            Mode::LookupLine
+        } else if !images.is_empty() {
+            Mode::SymbolicateImages
+        } else {
+            Mode::Symbolicate
+        };
+
+        let base_addr = [cli::Opt::LoadAddr, cli::Opt::SlideAddr]
+            .iter()
+            .find_map(|opt| args.get_one(&opt.to_string()))
+            .or_else(|| {
+                args.get_flag(&cli::Opt::Offset.to_string())
+                    .then_some(&Loc::Offset)
+            });
+
+        match mode {
+            Mode::SymbolicateImages if base_addr.is_some() => anyhow::bail!(
+                "-l, -s and --offset can't be used with binary images given with their load address"
+            ),
+            Mode::SymbolicateImages => (),
+            _ if !images.is_empty() => anyhow::bail!(
+                "Binary images given with their load address can only symbolicate addresses"
+            ),
+            Mode::PrintUuid | Mode::SymbolicateCrashReport => (),
+            _ if args
+                .get_many::<PathBuf>(&cli::Opt::Object.to_string())
+                .is_some_and(|paths| paths.len() > 1) =>
+            {
+                anyhow::bail!("-o can only be repeated with the load address of each binary image")
+            }
+            _ if base_addr.is_none() => {
+                anyhow::bail!("One of -l <load-address>, -s <slide> or --offset is required")
+            }
+            _ => (),
+        }
+
         Ok(Self {
-            mode: if args.get_flag(&cli::Opt::Uuid.to_string()) {
-                Mode::PrintUuid
-            } else {
-                Mode::Symbolicate
+            mode,
+
+            obj_path: match found_image
+                .as_ref()
+                .map(|image| &image.path)
+                .or_else(|| args.get_one::<PathBuf>(&cli::Opt::Object.to_string()))
+            {
+                // Printing UUIDs goes through every DWARF file of a dSYM, selecting none.
+                _ if !images.is_empty() || mode == Mode::PrintUuid => PathBuf::default(),
+                Some(path) => select_obj_path(path, uuid.filter(|_| found_image.is_none()), arch)?,
+                // Only crash reports can do without, finding their binary images by UUID.
+                None if args.contains_id(&cli::Opt::CrashReport.to_string()) => PathBuf::default(),
+                None => anyhow::bail!("No binary image path"),
             },
 
-            obj_path: {
-                let path = args
-                    .get_one::<PathBuf>(&cli::Opt::Object.to_string())
-                    .context("No binary image path")?;
-
-                if let Some(true) = path.extension().map(|ext| ext == "dSYM") {
-                    fs::read_dir(path.as_path().join("Contents/Resources/DWARF"))?
-                        .next()
-                        .context("No binary image path")??
-                        .path()
-                } else {
-                    path.clone()
-                }
+            obj_paths: match &found_image {
+                Some(image) => vec![image.path.clone()],
+                None => args
+                    .get_many(&cli::Opt::Object.to_string())
+                    .map(|paths| paths.cloned().collect())
+                    .unwrap_or_default(),
             },
 
-            base_addr: [cli::Opt::LoadAddr, cli::Opt::SlideAddr]
-                .iter()
-                .find_map(|opt| args.get_one(&opt.to_string()))
-                .unwrap_or(&Loc::Offset),
+            images,
+
+            crash_report: args
+                .get_one::<PathBuf>(&cli::Opt::CrashReport.to_string())
+                .map(PathBuf::as_path),
+
+            lookup_symbol: args
+                .get_one::<String>(&cli::Opt::LookupSymbol.to_string())
+                .map(String::as_str),
+
+            lookup_line: args.get_one(&cli::Opt::LookupLine.to_string()),
+
+            search_dirs,
+
+            base_addr: base_addr.unwrap_or(&Loc::Offset),
 
             addrs: {
                 let addr_list = args.get_many(&cli::Opt::Addr.to_string());
                 let addr_file = args.get_one::<PathBuf>(&cli::Opt::AddrFile.to_string());
 
                 match (addr_list, addr_file) {
-                    (Some(list), _) => Either::Left(list.copied().collect()),
-                    (_, Some(file)) => Either::Right(file),
-                    _ => Either::Left(Vec::default()),
+                    (Some(list), _) => Addrs::List(list.copied().collect()),
+                    (_, Some(file)) => Addrs::File(file),
+                    _ => Addrs::Stdin,
                 }
             },
 
-            arch: args
-                .get_one(&cli::Opt::Arch.to_string())
-                .map(String::as_str)
-                .map(Architecture::from_name),
+            addr_input: if args.get_flag(&cli::Opt::Extract.to_string()) {
+                AddrInput::Extract
+            } else {
+                AddrInput::Tokens
+            },
+
+            arch,
 
             include_inlined: args.get_flag(&cli::Opt::Inline.to_string()),
 
@@ -125,6 +265,186 @@ impl<'a> Context<'a> {
             show_full_path: args.get_flag(&cli::Opt::FullPath.to_string()),
 
             prefix_addrs: args.get_flag(&cli::Opt::PrefixAddr.to_string()),
+
+            show_column: args.get_flag(&cli::Opt::Column.to_string()),
+
+            dwo_dirs: dwo_dirs(args),
+
+            debug_dirs: debug_dirs(args),
+
+            cache_dir: cache_dir(args),
+
+            jobs: args
+                .get_one(&cli::Opt::Jobs.to_string())
+                .copied()
+                .unwrap_or(NonZeroUsize::MIN),
+
+            format: match args
+                .get_one::<String>(&cli::Opt::Format.to_string())
+                .map(String::as_str)
+            {
+                Some("json") => Format::Json,
+                Some("ndjson") => Format::Ndjson,
+                _ => Format::Text,
+            },
         })
     }
+
+    /// The context of `ators serve`, with the directories in which to look for binary images
+    /// and their debug info, that the context of each request is made from.
+    pub fn from_serve_args(args: &'a clap::ArgMatches) -> Self {
+        Self {
+            mode: Mode::Symbolicate,
+            obj_path: PathBuf::default(),
+            obj_paths: Vec::default(),
+            images: Vec::default(),
+            crash_report: None,
+            lookup_symbol: None,
+            lookup_line: None,
+            search_dirs: search_dirs(args),
+            base_addr: &Loc::Offset,
+            addrs: Addrs::List(Vec::default()),
+            addr_input: AddrInput::Tokens,
+            arch: None,
+            include_inlined: false,
+            delimiter: "",
+            show_full_path: false,
+            prefix_addrs: false,
+            show_column: false,
+            dwo_dirs: dwo_dirs(args),
+            debug_dirs: debug_dirs(args),
+            cache_dir: cache_dir(args),
+            jobs: NonZeroUsize::MIN,
+            format: Format::Json,
+        }
+    }
+
+    /// The context of `request` to the server of this context, with the binary image with the
+    /// UUID of the request found in `index`.
+    pub fn with_request(&self, request: &'a Request, index: &UuidIndex) -> Result<Self> {
+        let found_image = request
+            .uuid
+            .filter(|_| request.obj_path.is_none())
+            .map(|uuid| {
+                index
+                    .find(uuid.as_bytes())
+                    .with_context(|| format!("No binary image with UUID {uuid:X}"))
+            })
+            .transpose()?;
+
+        let arch = match found_image {
+            Some(image) => Some(image.arch),
+            None => request.arch.as_deref().map(Arch::from_name),
+        };
+
+        let uuid = request.uuid.as_ref().filter(|_| found_image.is_none());
+        let obj_path = found_image
+            .map(|image| &image.path)
+            .or(request.obj_path.as_ref())
+            .context("No binary image path or UUID")?;
+
+        Ok(Self {
+            obj_path: select_obj_path(obj_path, uuid, arch)?,
+            obj_paths: vec![obj_path.clone()],
+            base_addr: &request.base_addr,
+            addrs: Addrs::List(request.addrs.clone()),
+            arch,
+            include_inlined: request.include_inlined,
+            show_full_path: request.show_full_path,
+            show_column: request.show_column,
+            ..self.clone()
+        })
+    }
+}
+
+/// The DWARF file with `uuid` and `arch` of the .dSYM at `path`, or `path` itself when it's
+/// not a .dSYM, which a `uuid` can't select from.
+fn select_obj_path(path: &Path, uuid: Option<&Uuid>, arch: Option<Arch>) -> Result<PathBuf> {
+    Ok(if Dsym::is_dsym(path) {
+        Dsym::open(path)?
+            .select(uuid.map(Uuid::as_bytes), arch)?
+            .to_path_buf()
+    } else if let Some(uuid) = uuid {
+        anyhow::bail!(
+            "The UUID {uuid:X} can only select a DWARF file of a .dSYM, not {}",
+            path.display()
+        )
+    } else {
+        path.to_path_buf()
+    })
+}
+
+/// The binary images given with their load address, either all given with -o or none, along
+/// with the ones listed in the file given with --images.
+fn images(args: &clap::ArgMatches, arch: Option<Arch>) -> Result<Vec<(PathBuf, Addr)>> {
+    let obj_paths = args
+        .get_many::<PathBuf>(&cli::Opt::Object.to_string())
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+    let mut images = obj_paths
+        .iter()
+        .filter_map(|path| split_load_addr(path))
+        .collect::<Vec<_>>();
+
+    if let Some(path) = obj_paths
+        .iter()
+        .find(|path| !images.is_empty() && split_load_addr(path).is_none())
+    {
+        anyhow::bail!("No load address for binary image {}", path.display());
+    }
+
+    if let Some(file) = args.get_one::<PathBuf>(&cli::Opt::Images.to_string()) {
+        for (index, line) in fs::read_to_string(file)?.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            images.push(split_load_addr(Path::new(line)).with_context(|| {
+                format!(
+                    "No load address for binary image at line {}: {line}",
+                    index + 1
+                )
+            })?);
+        }
+    }
+
+    images
+        .into_iter()
+        .map(|(path, load_addr)| Ok((select_obj_path(&path, None, arch)?, load_addr)))
+        .collect()
+}
+
+/// Splits a `path@load-address` into the path and the load address.
+fn split_load_addr(path: &Path) -> Option<(PathBuf, Addr)> {
+    let (path, load_addr) = path.to_str()?.rsplit_once('@')?;
+    Some((PathBuf::from(path), load_addr.parse().ok()?))
+}
+
+fn search_dirs(args: &clap::ArgMatches) -> Vec<PathBuf> {
+    args.get_many(&cli::Opt::Search.to_string())
+        .map(|dirs| dirs.cloned().collect())
+        .unwrap_or_default()
+}
+
+fn dwo_dirs(args: &clap::ArgMatches) -> Vec<PathBuf> {
+    args.get_many(&cli::Opt::DwoDir.to_string())
+        .map(|dirs| dirs.cloned().collect())
+        .unwrap_or_default()
+}
+
+fn debug_dirs(args: &clap::ArgMatches) -> Vec<PathBuf> {
+    args.get_many(&cli::Opt::DebugDir.to_string())
+        .into_iter()
+        .flatten()
+        .cloned()
+        .chain([PathBuf::from(debug_file::DEFAULT_DEBUG_DIR)])
+        .collect()
+}
+
+fn cache_dir(args: &clap::ArgMatches) -> Option<&Path> {
+    args.get_one::<PathBuf>(&cli::Opt::CacheDir.to_string())
+        .map(PathBuf::as_path)
 }
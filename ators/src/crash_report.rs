@@ -0,0 +1,121 @@
+use crate::{context::Context, uuid_index};
+use anyhow::{Context as _, Result};
+use atorsl::{data::Loc, ext::object::File as _, *};
+use itertools::Itertools;
+use memmap2::Mmap;
+use object::Object;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Symbolicates the crash report of `ctx` against the binary images given, returning the
+/// report rewritten.
+///
+/// Each binary image of the report is matched by UUID to an architecture of a binary image,
+/// or of a DWARF file of a .dSYM, given or found in the search directories.
+pub fn symbolicate(ctx: &Context) -> Result<String> {
+    let path = ctx.crash_report.context("No crash report path")?;
+    let report = CrashReport::parse(&fs::read_to_string(path)?)?;
+
+    let mut paths = ctx
+        .obj_paths
+        .iter()
+        .map(|path| object_paths(path))
+        .flatten_ok()
+        .collect::<Result<Vec<_>>>()?;
+
+    if !ctx.search_dirs.is_empty() {
+        let index = uuid_index(&ctx.search_dirs)?;
+        paths.extend(
+            report
+                .images()
+                .iter()
+                .filter_map(|image| Some(index.find(&image.uuid?)?.path.clone())),
+        );
+        paths = paths.into_iter().unique().collect();
+    }
+
+    let mmaps = paths
+        .iter()
+        .map(|path| Ok(unsafe { Mmap::map(&fs::File::open(path)?) }?))
+        .collect::<Result<Vec<_>>>()?;
+
+    let objs = paths
+        .iter()
+        .zip(&mmaps)
+        .map(|(path, mmap)| {
+            Ok(object::File::parse_slices(mmap)?
+                .into_iter()
+                .map(move |(_, obj)| (path, obj)))
+        })
+        .flatten_ok()
+        .collect::<Result<Vec<_>>>()?;
+
+    let matches = report
+        .images()
+        .iter()
+        .enumerate()
+        .filter_map(|(index, image)| {
+            let uuid = image.uuid?;
+            let (path, obj) = objs
+                .iter()
+                .find(|(_, obj)| obj.mach_uuid().ok().flatten() == Some(uuid))?;
+
+            Some((index, path, obj))
+        })
+        .collect::<Vec<_>>();
+
+    let sections = matches
+        .iter()
+        .map(|(_, _, obj)| DwarfSections::load(obj))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let dwarfs = sections
+        .iter()
+        .map(DwarfSections::dwarf)
+        .collect::<Vec<_>>();
+
+    let debug_maps = matches
+        .iter()
+        .map(|(_, path, obj)| DebugMap::load(obj, path))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let images = matches
+        .iter()
+        .zip(dwarfs.iter().zip(&debug_maps))
+        .map(|((index, _, obj), (dwarf, debug_map))| {
+            let symbolicator = Symbolicator::new(dwarf)?.with_debug_map(debug_map);
+            Ok((*index, Image::new(obj, symbolicator)))
+        })
+        .collect::<Result<HashMap<_, _>, Error>>()?;
+
+    Ok(report.symbolicate(|index, addr| {
+        images
+            .get(&index)?
+            .symbolicate(
+                &Loc::Load(report.images()[index].load_addr),
+                [addr],
+                ctx.include_inlined,
+            )
+            .ok()?
+            .next()?
+            .result
+            .ok()
+            .map(|frames| frames.symbols)
+    })?)
+}
+
+/// The binary image at `path`, or every DWARF file of the .dSYM at `path`.
+fn object_paths(path: &Path) -> Result<Vec<PathBuf>> {
+    if Dsym::is_dsym(path) {
+        Ok(Dsym::open(path)?
+            .dwarf_files()
+            .iter()
+            .map(|dwarf_file| dwarf_file.path.clone())
+            .collect())
+    } else {
+        Ok(vec![path.to_path_buf()])
+    }
+}
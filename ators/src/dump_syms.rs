@@ -0,0 +1,45 @@
+use crate::cli;
+use anyhow::Result;
+use atorsl::{
+    breakpad, data::Arch, ext::object::File as _, load_dwarf, DebugMap, Dsym, Symbolicator,
+};
+use memmap2::Mmap;
+use std::{fs, path::PathBuf};
+
+/// Writes the binary image or dSYM given to the `dump-syms` subcommand as a Breakpad symbol
+/// file, reporting the functions left out of it on the standard error.
+pub fn dump(args: &clap::ArgMatches) -> Result<String> {
+    let path = args
+        .get_one::<PathBuf>(&cli::Opt::Object.to_string())
+        .expect("binary image path is required");
+
+    let arch = args
+        .get_one::<String>(&cli::Opt::Arch.to_string())
+        .map(|arch| Arch::from_name(arch));
+
+    let path = if Dsym::is_dsym(path) {
+        Dsym::open(path)?.select(None, arch)?.to_path_buf()
+    } else {
+        path.clone()
+    };
+
+    let mmap = unsafe { Mmap::map(&fs::File::open(&path)?) }?;
+    let obj = object::File::parse_data(&mmap, arch)?;
+
+    let cow;
+    let dwarf = load_dwarf!(&obj, cow);
+    let debug_map = DebugMap::load(&obj, &path)?;
+    let symbolicator = Symbolicator::new(&dwarf)?.with_debug_map(&debug_map);
+
+    let dump = breakpad::dump(
+        &obj,
+        &symbolicator,
+        &path.file_name().unwrap_or_default().to_string_lossy(),
+    )?;
+
+    for name in &dump.skipped {
+        eprintln!("Skipped {name}, which starts below the base address of the binary image");
+    }
+
+    Ok(dump.sym)
+}
@@ -0,0 +1,78 @@
+use crate::{
+    context::{Addrs, Context},
+    LossyFileName,
+};
+use anyhow::Result;
+use atorsl::{
+    data::{Addr, Frames, Source, Symbol},
+    BacktraceFrame, Error,
+};
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+    path::Path,
+};
+
+/// Prints the backtrace text of the input file of `ctx`, or of the standard input, with the
+/// frames of its binary image symbolicated in place by `atos`, against the binary image built
+/// at `vmaddr`.
+pub fn filter(
+    ctx: &Context,
+    vmaddr: Option<Addr>,
+    atos: impl Fn(Addr) -> Result<Frames, Error>,
+) -> Result<()> {
+    let reader: Box<dyn BufRead> = match &ctx.addrs {
+        Addrs::File(file) => Box::new(io::BufReader::new(fs::File::open(file)?)),
+        _ => Box::new(io::stdin().lock()),
+    };
+
+    let mut stdout = io::stdout().lock();
+    for line in reader.lines() {
+        let line = line?;
+        let rewritten = BacktraceFrame::parse(&line).and_then(|frame| {
+            let symbols = symbolicate(&frame, ctx, vmaddr, &atos)?;
+            Some(frame.rewrite(&line, &symbols))
+        });
+
+        writeln!(stdout, "{}", rewritten.as_deref().unwrap_or(&line))?;
+        stdout.flush()?;
+    }
+
+    Ok(())
+}
+
+/// The symbols of `frame`, when it's a frame of the binary image of `ctx`, named after it, or
+/// after its start in a truncated image column.
+///
+/// A frame that names no image is taken as one of the binary image only when its address is
+/// in the debug info, as any address past the last symbol of the symbol table would be
+/// symbolicated to it.
+fn symbolicate(
+    frame: &BacktraceFrame,
+    ctx: &Context,
+    vmaddr: Option<Addr>,
+    atos: impl Fn(Addr) -> Result<Frames, Error>,
+) -> Option<Vec<Symbol>> {
+    if let Some(image) = &frame.image {
+        let image = Path::new(image).lossy_file_name();
+        let obj_name = ctx.obj_path.lossy_file_name();
+        let matches = match frame.image_truncated {
+            true => obj_name.starts_with(&*image),
+            false => obj_name == image,
+        };
+
+        if image.is_empty() || !matches {
+            return None;
+        }
+    }
+
+    let base_addr = frame.base_addr.as_ref().unwrap_or(ctx.base_addr);
+    let addr = frame
+        .addr
+        .checked_add_signed(base_addr.addr_offset(vmaddr).ok()?)?;
+    let frames = atos(Addr::from(addr)).ok()?;
+
+    Some(frames.symbols).filter(|symbols| {
+        !symbols.is_empty() && (frame.image.is_some() || frames.source != Source::SymbolTable)
+    })
+}
@@ -0,0 +1,128 @@
+use crate::{
+    context::{Context, Format},
+    format_symbolications, json,
+    serve::LoadedImage,
+    with_addrs,
+};
+use anyhow::{Context as _, Result};
+use atorsl::{
+    data::{Loc, Symbolication},
+    Error,
+};
+use itertools::Itertools;
+use std::{iter, ops::Range, path::Path};
+
+/// A binary image given with its load address.
+struct ImageAt<'ctx> {
+    /// The context of `ctx` for the binary image, with its load address, for addresses to be
+    /// symbolicated and printed in it.
+    ctx: Context<'ctx>,
+
+    image: LoadedImage,
+
+    /// The range its text was loaded at.
+    text: Range<u64>,
+}
+
+/// Symbolicates the addresses of `ctx` across the binary images given with their load address,
+/// each in the one whose text it falls in, and prints them.
+///
+/// Each binary image is loaded as the one of a single `-o` is, along with its debug files, or
+/// from a Breakpad symbol file.
+pub fn symbolicate(ctx: &Context) -> Result<()> {
+    let load_addrs = ctx
+        .images
+        .iter()
+        .map(|(_, load_addr)| Loc::Load(*load_addr))
+        .collect::<Vec<_>>();
+
+    let images = ctx
+        .images
+        .iter()
+        .zip(&load_addrs)
+        .map(|((path, _), load_addr)| {
+            load(ctx, path, load_addr).with_context(|| format!("Cannot load {}", path.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    with_addrs(ctx, |addrs| {
+        let symbolications = addrs
+            .into_iter()
+            .map(|input_addr| {
+                let Some(image) = images
+                    .iter()
+                    .find(|image| image.text.contains(&input_addr))
+                else {
+                    let symbolication = Symbolication {
+                        input_addr,
+                        addr: None,
+                        result: Err(Error::AddrImageNotFound(input_addr)),
+                    };
+
+                    return Ok(vec![(symbolication, ctx)]);
+                };
+
+                Ok(image
+                    .image
+                    .symbolicate(&image.ctx, vec![input_addr])?
+                    .into_iter()
+                    .map(|symbolication| (symbolication, &image.ctx))
+                    .collect())
+            })
+            .flatten_ok()
+            .collect::<Result<Vec<_>>>()?;
+
+        print_symbolications(symbolications.into_iter(), ctx);
+        Ok(())
+    })
+}
+
+/// Loads the binary image at `path`, loaded at `load_addr`.
+fn load<'ctx>(ctx: &Context<'ctx>, path: &Path, load_addr: &'ctx Loc) -> Result<ImageAt<'ctx>> {
+    let image = LoadedImage::load(&Context {
+        obj_path: path.to_path_buf(),
+        ..ctx.clone()
+    })?;
+
+    let text = image
+        .text
+        .clone()
+        .ok_or(Error::VmAddrTextSegmentNotFound)?;
+
+    let addr_offset = load_addr.addr_offset(image.vmaddr)?;
+
+    Ok(ImageAt {
+        ctx: Context {
+            obj_path: image.obj_path.clone(),
+            base_addr: load_addr,
+            ..ctx.clone()
+        },
+        text: text.start.wrapping_add_signed(-addr_offset)
+            ..text.end.wrapping_add_signed(-addr_offset),
+        image,
+    })
+}
+
+/// Prints the symbolications of each address as in the context of the binary image they were
+/// symbolicated in.
+fn print_symbolications<'a, 'ctx: 'a>(
+    symbolications: impl Iterator<Item = (Symbolication, &'a Context<'ctx>)>,
+    ctx: &Context,
+) {
+    match ctx.format {
+        Format::Text => symbolications
+            .flat_map(|(symbolication, ctx)| format_symbolications(iter::once(symbolication), ctx))
+            .for_each(|symbol| println!("{symbol}")),
+
+        Format::Json => println!(
+            "{:#}",
+            symbolications
+                .map(|(symbolication, ctx)| json::symbolication(&symbolication, ctx))
+                .collect::<serde_json::Value>()
+        ),
+
+        Format::Ndjson => symbolications.for_each(|(symbolication, ctx)| {
+            println!("{}", json::symbolication(&symbolication, ctx))
+        }),
+    }
+}
@@ -0,0 +1,135 @@
+use crate::{context::Context, LossyFileName};
+use atorsl::data::{Addr, Arch, LineRange, Source, Symbol, SymbolRanges, Symbolication};
+use itertools::Either;
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// The JSON object of a symbolicated address, with the frames it was symbolicated to, or the
+/// error symbolicating it.
+pub fn symbolication(symbolication: &Symbolication, ctx: &Context) -> Value {
+    let mut value = json!({
+        "input_address": addr(symbolication.input_addr),
+        "address": symbolication.addr.map(addr),
+        "image": ctx.obj_path.lossy_file_name(),
+    });
+
+    match &symbolication.result {
+        Ok(frames) => {
+            value["frames"] = frames
+                .symbols
+                .iter()
+                .map(|symbol| frame(symbol, frames.source, ctx))
+                .collect();
+        }
+        Err(err) => {
+            value["frames"] = json!([]);
+            value["error"] = json!(err.to_string());
+        }
+    }
+
+    value
+}
+
+/// The JSON object of a function found by name, with the address ranges of its code.
+pub fn symbol_ranges(found: &SymbolRanges, ctx: &Context) -> Value {
+    let (inlined_into, call_loc) = match &found.inlined_into {
+        Some((caller, call_loc)) => (Some(caller), Some(call_loc)),
+        None => (None, None),
+    };
+
+    json!({
+        "symbol": found.name,
+        "mangled_name": found.mangled_name,
+        "image": ctx.obj_path.lossy_file_name(),
+        "ranges": found
+            .ranges
+            .iter()
+            .map(|range| json!({ "start": addr(range.start), "end": addr(range.end) }))
+            .collect::<Value>(),
+        "inlined_into": inlined_into,
+        "call_file": call_loc.map(|call_loc| if ctx.show_full_path {
+            call_loc.file.to_string_lossy()
+        } else {
+            call_loc.file.lossy_file_name()
+        }),
+        "call_line": call_loc.map(|call_loc| call_loc.line),
+        "call_column": call_loc.map(|call_loc| call_loc.col),
+        "source": source_name(found.source),
+    })
+}
+
+/// The JSON object of a range of code of a source line, with the frames of the code, or the
+/// error finding them.
+pub fn line_range(found: &LineRange, ctx: &Context) -> Value {
+    let mut value = json!({
+        "start": addr(found.range.start),
+        "end": addr(found.range.end),
+        "image": ctx.obj_path.lossy_file_name(),
+        "file": if ctx.show_full_path {
+            found.loc.file.to_string_lossy()
+        } else {
+            found.loc.file.lossy_file_name()
+        },
+        "line": found.loc.line,
+        "column": found.loc.col,
+    });
+
+    match &found.symbols {
+        Ok(symbols) => {
+            value["frames"] = symbols
+                .iter()
+                .map(|symbol| frame(symbol, Source::Dwarf, ctx))
+                .collect();
+        }
+        Err(err) => {
+            value["frames"] = json!([]);
+            value["error"] = json!(err.to_string());
+        }
+    }
+
+    value
+}
+
+/// The JSON object of the UUID of an architecture of a binary image.
+pub fn uuid(uuid: &str, arch: &Arch, path: &Path) -> Value {
+    json!({
+        "uuid": uuid,
+        "arch": arch.name(),
+        "path": path.to_string_lossy(),
+    })
+}
+
+fn frame(symbol: &Symbol, source: Source, ctx: &Context) -> Value {
+    let (source_loc, offset) = match &symbol.loc {
+        Either::Left(source_loc) => (Some(source_loc), None),
+        Either::Right(offset) => (None, Some(**offset)),
+    };
+
+    json!({
+        "symbol": symbol.name,
+        "mangled_name": symbol.mangled_name,
+        "file": source_loc.map(|source_loc| if ctx.show_full_path {
+            source_loc.file.to_string_lossy()
+        } else {
+            source_loc.file.lossy_file_name()
+        }),
+        "line": source_loc.map(|source_loc| source_loc.line),
+        "column": source_loc.map(|source_loc| source_loc.col),
+        "offset": offset,
+        "inlined": symbol.inlined,
+        "source": source_name(source),
+    })
+}
+
+fn source_name(source: Source) -> &'static str {
+    match source {
+        Source::Dwarf => "dwarf",
+        Source::Pdb => "pdb",
+        Source::Breakpad => "breakpad",
+        Source::SymbolTable => "symtab",
+    }
+}
+
+fn addr(addr: Addr) -> String {
+    format!("{:#x}", *addr)
+}
@@ -2,88 +2,296 @@
 
 mod cli;
 mod context;
+mod crash_report;
+mod dump_syms;
+mod filter;
+mod images;
+mod json;
+mod serve;
 
-use anyhow::{Context as _, Result};
+use anyhow::Result;
 use atorsl::{
-    data::{Addr, Symbol},
-    ext::object::{Architecture as _, File as _},
+    data::{Addr, Arch, Frames, LineRange, SourceLoc, Symbol, SymbolRanges, Symbolication},
+    ext::object::File as _,
     *,
 };
-use context::{Context, Loc, Mode};
-use fallible_iterator::FallibleIterator;
+use context::{Addrs, Context, Format, Mode};
 use itertools::{Either, Itertools};
 use memmap2::Mmap;
 use object::Object;
 use std::{
     borrow::Cow,
     fs,
-    io::{self, BufRead},
-    path::Path,
-    str,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+    process, str,
 };
 use uuid::Uuid;
 
 fn main() -> Result<()> {
     let args = cli::build().get_matches();
-    let ctx = Context::from_args(&args)?;
+    match args.subcommand() {
+        Some((cli::DUMP_SYMS, args)) => {
+            print!("{}", dump_syms::dump(args)?);
+            return Ok(());
+        }
+        Some((cli::SERVE, args)) => return serve::serve(args),
+        _ => (),
+    }
 
-    let mmap = unsafe { Mmap::map(&fs::File::open(&ctx.obj_path)?) }?;
-    let obj = object::File::parse_data(&mmap, ctx.arch)?;
+    let ctx = Context::from_args(&args)?;
 
     match ctx.mode {
-        Mode::Symbolicate => {
+        Mode::Symbolicate | Mode::Filter if BreakpadSym::is_sym(&ctx.obj_path) => {
+            let sym = BreakpadSym::parse(&fs::read_to_string(&ctx.obj_path)?)?;
+
+            // Symbols are printed as in the binary image the symbol file was written from.
+            let ctx = Context {
+                obj_path: PathBuf::from(&sym.module.name),
+                ..ctx.clone()
+            };
+
+            symbolicate_or_filter(&ctx, Some(sym.base_addr), |addr| {
+                sym.atos(addr, ctx.include_inlined)
+            })?;
+        }
+
+        Mode::LookupSymbol | Mode::LookupLine if BreakpadSym::is_sym(&ctx.obj_path) => {
+            anyhow::bail!(
+                "--lookup-symbol and --lookup-line can't be used with Breakpad symbol files"
+            )
+        }
+
+        Mode::Symbolicate | Mode::Filter | Mode::LookupSymbol | Mode::LookupLine => {
+            let mmap = unsafe { Mmap::map(&fs::File::open(&ctx.obj_path)?) }?;
+            let obj = object::File::parse_data(&mmap, ctx.arch)?;
+
+            let debug_path = if obj.has_debug_symbols() {
+                None
+            } else {
+                debug_file::find(&obj, &ctx.obj_path, &ctx.debug_dirs)?
+            };
+
+            let debug_mmap;
+            let obj = match debug_path {
+                Some(debug_path) => {
+                    debug_mmap = unsafe { Mmap::map(&fs::File::open(debug_path)?) }?;
+                    object::File::parse_data(&debug_mmap, ctx.arch)?
+                }
+                None => obj,
+            };
+
+            // The symbol cache only holds what's indexed from the DWARF and the symbol table,
+            // by address, so it's left aside for images debugged with a PDB. On a hit, the
+            // debug info is never loaded.
+            let symcache_path = match ctx.cache_dir {
+                Some(cache_dir)
+                    if matches!(ctx.mode, Mode::Symbolicate | Mode::Filter)
+                        && obj.pdb_info()?.is_none() =>
+                {
+                    SymCache::file_name(&obj)?.map(|file_name| cache_dir.join(file_name))
+                }
+                _ => None,
+            };
+
+            if let Some(symcache_path) = symcache_path {
+                let symcache_mmap = map_symcache(&symcache_path, &obj, &ctx)?;
+                let symcache = SymCache::parse(&symcache_mmap)?;
+                return symbolicate_or_filter(&ctx, obj.vmaddr().ok(), |addr| {
+                    symcache.atos(addr, ctx.include_inlined)
+                });
+            }
+
             let cow;
             let dwarf = load_dwarf!(&obj, cow);
-            let addrs = compute_addrs(&obj, &ctx)?;
+            let split_dwarf = SplitDwarf::load(&dwarf, &ctx.obj_path, &ctx.dwo_dirs)?;
+            let debug_map = DebugMap::load(&obj, &ctx.obj_path)?;
+            let symbolicator =
+                Symbolicator::with_split_dwarf(&dwarf, &split_dwarf)?.with_debug_map(&debug_map);
 
-            symbolicate(&dwarf, &obj.symbol_map(), &addrs, &ctx)
-                .iter()
-                .for_each(|symbol| println!("{symbol}"));
-        }
+            let pdb_file = PdbFile::find(&obj, &ctx.obj_path, &ctx.debug_dirs)?
+                .map(|pdb_path| PdbFile::load(&obj, &pdb_path))
+                .transpose()?;
 
-        Mode::PrintUuid => {
-            println!(
-                "    {:X} {:#8} {}",
-                Uuid::from_bytes(obj.mach_uuid()?.ok_or(Error::ObjectHasNoUuid)?).hyphenated(),
-                obj.architecture().name(),
-                ctx.obj_path.to_string_lossy(),
-            );
+            let image = match &pdb_file {
+                Some(pdb_file) => Image::new(&obj, symbolicator).with_pdb(pdb_file),
+                None => Image::new(&obj, symbolicator),
+            };
+
+            if let Some(lookup_symbol) = ctx.lookup_symbol {
+                let query = SymbolQuery::new(lookup_symbol);
+                print_symbol_ranges(&image.lookup_symbol(ctx.base_addr, &query)?, &ctx);
+                return Ok(());
+            }
+
+            if let Some((file, line)) = ctx.lookup_line {
+                print_line_ranges(&image.lookup_line(ctx.base_addr, file, *line)?, &ctx);
+                return Ok(());
+            }
+
+            if ctx.mode == Mode::Filter {
+                return filter::filter(&ctx, obj.vmaddr().ok(), |addr| {
+                    image.atos(addr, ctx.include_inlined)
+                });
+            }
+
+            with_addrs(&ctx, |addrs| {
+                let symbolications = if ctx.jobs.get() > 1 {
+                    Either::Left(
+                        image
+                            .symbolicate_parallel(
+                                ctx.base_addr,
+                                &addrs,
+                                ctx.include_inlined,
+                                ctx.jobs,
+                            )?
+                            .into_iter(),
+                    )
+                } else {
+                    Either::Right(image.symbolicate(ctx.base_addr, addrs, ctx.include_inlined)?)
+                };
+
+                print_symbolications(symbolications, &ctx);
+                Ok(())
+            })?;
         }
+
+        Mode::SymbolicateCrashReport => print!("{}", crash_report::symbolicate(&ctx)?),
+
+        Mode::SymbolicateImages => images::symbolicate(&ctx)?,
+
+        Mode::PrintUuid => print_uuids(&ctx)?,
     }
 
     Ok(())
 }
 
-fn symbolicate(
-    dwarf: &Dwarf,
-    symbol_map: &object::SymbolMap<object::SymbolMapName>,
-    addrs: &[Addr],
+/// Symbolicates the addresses of `ctx` with `atos`, against a binary image built at `vmaddr`,
+/// and prints them, or filters the backtrace text of `ctx` with it.
+fn symbolicate_or_filter(
     ctx: &Context,
-) -> Vec<String> {
-    let iter_symbols = addrs
-        .iter()
-        .map(|addr| {
-            let symbols = match atos_dwarf(dwarf, *addr, ctx.include_inlined) {
-                Err(
-                    Error::AddrNotFound(addr)
-                    | Error::AddrDebugInfoOffsetMissing(addr)
-                    | Error::AddrLineInfoMissing(addr),
-                ) => atos_map(symbol_map, addr)?,
-                symbols => symbols?,
-            };
+    vmaddr: Option<Addr>,
+    atos: impl Fn(Addr) -> Result<Frames, Error>,
+) -> Result<()> {
+    if ctx.mode == Mode::Filter {
+        return filter::filter(ctx, vmaddr, atos);
+    }
 
-            let symbol = symbols
-                .iter()
-                .map(|symbol| format(symbol, ctx))
-                .join("\n");
+    with_addrs(ctx, |addrs| {
+        let symbolications = symbolicate_with(ctx, addrs, vmaddr, &atos)?;
+        print_symbolications(symbolications.into_iter(), ctx);
+        Ok(())
+    })
+}
+
+/// Symbolicates `addrs`, given relative to the base address of `ctx`, with `atos`, against a
+/// binary image built at `vmaddr`.
+fn symbolicate_with(
+    ctx: &Context,
+    addrs: Vec<Addr>,
+    vmaddr: Option<Addr>,
+    atos: impl Fn(Addr) -> Result<Frames, Error>,
+) -> Result<Vec<Symbolication>> {
+    let addr_offset = ctx.base_addr.addr_offset(vmaddr)?;
 
-            Ok(symbol)
+    Ok(addrs
+        .into_iter()
+        .map(|input_addr| {
+            let addr = input_addr
+                .checked_add_signed(addr_offset)
+                .map(Addr::from);
+
+            Symbolication {
+                input_addr,
+                addr,
+                result: addr
+                    .ok_or(Error::AddrInvalid(input_addr))
+                    .and_then(&atos),
+            }
         })
-        .map(|symbol| match symbol {
-            Ok(symbol) => symbol,
-            Err(Error::AddrNotFound(addr)) => addr.to_string(),
-            Err(err) => err.to_string(),
-        });
+        .collect())
+}
+
+/// Indexes the binary images in `search_dirs` by UUID, reporting the subdirectories and files
+/// that couldn't be read on the standard error.
+fn uuid_index(search_dirs: &[PathBuf]) -> Result<UuidIndex> {
+    let index = UuidIndex::index(search_dirs)?;
+    for (path, err) in index.skipped() {
+        eprintln!("Cannot read {}: {err}", path.to_string_lossy());
+    }
+
+    Ok(index)
+}
+
+/// Maps the symbol cache at `path`, writing it first from the debug info of `obj`, the binary
+/// image of `ctx`, unless a valid one is there already.
+fn map_symcache(path: &Path, obj: &object::File, ctx: &Context) -> Result<Mmap> {
+    if let Ok(file) = fs::File::open(path) {
+        let mmap = unsafe { Mmap::map(&file) }?;
+        if SymCache::parse(&mmap)
+            .and_then(|symcache| symcache.check())
+            .is_ok()
+        {
+            return Ok(mmap);
+        }
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    // Written aside and renamed into place, for concurrent runs to never map half a cache.
+    let tmp_path = path.with_extension(format!("{}.tmp", process::id()));
+    write_symcache(obj, ctx, fs::File::create(&tmp_path)?)?;
+    fs::rename(&tmp_path, path)?;
+
+    Ok(unsafe { Mmap::map(&fs::File::open(path)?) }?)
+}
+
+/// Writes the symbol cache of `obj`, the binary image of `ctx`, to `writer`, loading and
+/// indexing its debug info.
+fn write_symcache(obj: &object::File, ctx: &Context, writer: impl Write) -> Result<()> {
+    let cow;
+    let dwarf = load_dwarf!(obj, cow);
+    let split_dwarf = SplitDwarf::load(&dwarf, &ctx.obj_path, &ctx.dwo_dirs)?;
+    let debug_map = DebugMap::load(obj, &ctx.obj_path)?;
+    let symbolicator =
+        Symbolicator::with_split_dwarf(&dwarf, &split_dwarf)?.with_debug_map(&debug_map);
+
+    Ok(SymCache::write(obj, &symbolicator, writer)?)
+}
+
+fn print_symbolications(symbolications: impl Iterator<Item = Symbolication>, ctx: &Context) {
+    match ctx.format {
+        Format::Text => format_symbolications(symbolications, ctx)
+            .iter()
+            .for_each(|symbol| println!("{symbol}")),
+
+        Format::Json => println!(
+            "{:#}",
+            symbolications
+                .map(|symbolication| json::symbolication(&symbolication, ctx))
+                .collect::<serde_json::Value>()
+        ),
+
+        Format::Ndjson => symbolications
+            .for_each(|symbolication| println!("{}", json::symbolication(&symbolication, ctx))),
+    }
+}
+
+fn format_symbolications(
+    symbolications: impl Iterator<Item = Symbolication>,
+    ctx: &Context,
+) -> Vec<String> {
+    let iter_symbols = symbolications.map(|symbolication| match symbolication.result {
+        Ok(frames) => frames
+            .symbols
+            .iter()
+            .map(|symbol| format(symbol, ctx))
+            .join("\n"),
+        Err(Error::AddrNotFound(addr)) => addr.to_string(),
+        Err(err) => err.to_string(),
+    });
 
     if ctx.include_inlined {
         iter_symbols
@@ -105,16 +313,11 @@ fn format(symbol: &Symbol, ctx: &Context) -> String {
     match symbol.loc.as_ref() {
         Either::Left(source_loc) => {
             format!(
-                "{}{} (in {}) ({}:{})",
+                "{}{} (in {}) ({})",
                 symbol_addr_fmt,
                 symbol.name,
                 ctx.obj_path.lossy_file_name(),
-                if ctx.show_full_path {
-                    source_loc.file.to_string_lossy()
-                } else {
-                    source_loc.file.lossy_file_name()
-                },
-                source_loc.line,
+                format_source_loc(source_loc, ctx),
             )
         }
         Either::Right(offset) => {
@@ -129,31 +332,237 @@ fn format(symbol: &Symbol, ctx: &Context) -> String {
     }
 }
 
-fn compute_addrs(obj: &object::File, ctx: &Context) -> Result<Vec<Addr>> {
-    let addrs = match &ctx.addrs {
-        Either::Left(addrs) => addrs.clone(),
-        Either::Right(file) => io::BufReader::new(fs::File::open(file)?)
-            .split(b' ')
-            .flat_map(|buf| Addr::try_from(&*buf?))
-            .collect(),
+fn format_source_loc(source_loc: &SourceLoc, ctx: &Context) -> String {
+    format!(
+        "{}:{}{}",
+        if ctx.show_full_path {
+            source_loc.file.to_string_lossy()
+        } else {
+            source_loc.file.lossy_file_name()
+        },
+        source_loc.line,
+        if ctx.show_column && source_loc.col != 0 {
+            format!(":{}", source_loc.col)
+        } else {
+            String::default()
+        },
+    )
+}
+
+/// Prints the address ranges of the functions found by [`Image::lookup_symbol`], a line per
+/// range.
+fn print_symbol_ranges(found: &[SymbolRanges], ctx: &Context) {
+    match ctx.format {
+        Format::Text => found
+            .iter()
+            .flat_map(|found| {
+                found.ranges.iter().map(|range| {
+                    format!(
+                        "{} {} (in {}){}",
+                        if range.is_empty() {
+                            range.start.to_string()
+                        } else {
+                            format!("{}-{}", range.start, range.end)
+                        },
+                        found.name,
+                        ctx.obj_path.lossy_file_name(),
+                        match &found.inlined_into {
+                            Some((caller, call_loc)) => format!(
+                                " (inlined into {} at {})",
+                                caller,
+                                format_source_loc(call_loc, ctx)
+                            ),
+                            None => String::default(),
+                        },
+                    )
+                })
+            })
+            .for_each(|range| println!("{range}")),
+
+        Format::Json => println!(
+            "{:#}",
+            found
+                .iter()
+                .map(|found| json::symbol_ranges(found, ctx))
+                .collect::<serde_json::Value>()
+        ),
+
+        Format::Ndjson => found
+            .iter()
+            .for_each(|found| println!("{}", json::symbol_ranges(found, ctx))),
+    }
+}
+
+/// Prints the ranges of code found by [`Image::lookup_line`], each followed by its frames.
+fn print_line_ranges(found: &[LineRange], ctx: &Context) {
+    match ctx.format {
+        Format::Text => found.iter().for_each(|found| {
+            println!(
+                "{}-{} {}",
+                found.range.start,
+                found.range.end,
+                format_source_loc(&found.loc, ctx)
+            );
+
+            match &found.symbols {
+                Ok(symbols) => symbols
+                    .iter()
+                    .for_each(|symbol| println!("    {}", format(symbol, ctx))),
+                Err(err) => println!("    {err}"),
+            }
+        }),
+
+        Format::Json => println!(
+            "{:#}",
+            found
+                .iter()
+                .map(|found| json::line_range(found, ctx))
+                .collect::<serde_json::Value>()
+        ),
+
+        Format::Ndjson => found
+            .iter()
+            .for_each(|found| println!("{}", json::line_range(found, ctx))),
+    }
+}
+
+/// Prints the UUID of each architecture of the binary images of `ctx`, and of each DWARF file
+/// of its dSYMs, as `dwarfdump --uuid` does, or only of the architecture given with `--arch`.
+///
+/// Each file whose UUIDs can't be read is reported on the standard error, and fails the run
+/// once the UUIDs of the others are printed.
+fn print_uuids(ctx: &Context) -> Result<()> {
+    let mut uuids = Vec::default();
+    let mut failed = 0;
+    let mut report = |path: &Path, err: anyhow::Error| {
+        eprintln!("{}: {err:#}", path.to_string_lossy());
+        failed += 1;
     };
 
-    let offset_addr = match ctx.base_addr {
-        Loc::Offset => *obj.vmaddr()? as i64,
-        Loc::Slide(slide) => -(**slide as i64),
-        Loc::Load(load_addr) => {
-            -(load_addr
-                .checked_sub(*obj.vmaddr()?)
-                .context(format!("Invalid load address: {}", load_addr))? as i64)
+    for obj_path in &ctx.obj_paths {
+        let paths = if Dsym::is_dsym(obj_path) {
+            match Dsym::open(obj_path) {
+                Ok(dsym) => dsym
+                    .dwarf_files()
+                    .iter()
+                    .map(|dwarf_file| dwarf_file.path.clone())
+                    .collect(),
+                Err(err) => {
+                    report(obj_path, err.into());
+                    continue;
+                }
+            }
+        } else {
+            vec![obj_path.clone()]
+        };
+
+        for path in paths {
+            match object_uuids(&path, ctx) {
+                Ok(found) => uuids.extend(found),
+                Err(err) => report(&path, err),
+            }
         }
-    };
+    }
+
+    if let (Some(arch), true, 0) = (ctx.arch, uuids.is_empty(), failed) {
+        anyhow::bail!("No binary image of architecture {arch}");
+    }
+
+    match ctx.format {
+        Format::Text => uuids.iter().for_each(|(uuid, arch, path)| {
+            println!("    {} {:#8} {}", uuid, arch.name(), path.to_string_lossy())
+        }),
+
+        Format::Json => println!(
+            "{:#}",
+            uuids
+                .iter()
+                .map(|(uuid, arch, path)| json::uuid(uuid, arch, path))
+                .collect::<serde_json::Value>()
+        ),
+
+        Format::Ndjson => uuids
+            .iter()
+            .for_each(|(uuid, arch, path)| println!("{}", json::uuid(uuid, arch, path))),
+    }
+
+    if failed > 0 {
+        anyhow::bail!("Cannot read the UUIDs of {failed} binary images");
+    }
+
+    Ok(())
+}
+
+/// The UUID of each architecture of the binary image at `path`, or only of the architecture
+/// given with `--arch`.
+fn object_uuids(path: &Path, ctx: &Context) -> Result<Vec<(String, Arch, PathBuf)>> {
+    let mmap = unsafe { Mmap::map(&fs::File::open(path)?) }?;
+    let mut slices = object::File::parse_slices(&mmap)?;
+
+    if let Some(selected_arch) = ctx.arch {
+        // A slice of the very architecture is preferred, as when selecting one.
+        let exact = slices.iter().any(|(arch, _)| *arch == selected_arch);
+        slices.retain(|(arch, _)| {
+            if exact {
+                *arch == selected_arch
+            } else {
+                selected_arch.matches(arch)
+            }
+        });
+    }
+
+    slices
+        .into_iter()
+        .map(|(arch, obj)| Ok((format_uuid(&obj)?, arch, path.to_path_buf())))
+        .collect()
+}
+
+/// Formats the UUID of `obj`, as `symbols -uuid` prints it, or its build ID, or the GUID and
+/// age of its PDB.
+fn format_uuid(obj: &object::File) -> Result<String, Error> {
+    Ok(match (obj.mach_uuid()?, obj.build_id()?, obj.pdb_info()?) {
+        (Some(uuid), _, _) => format!("{:X}", Uuid::from_bytes(uuid).hyphenated()),
+        (None, Some(build_id), _) => debug_file::hex(build_id),
+        (None, None, Some(code_view)) => format!(
+            "{:X}{:x}",
+            Uuid::from_bytes_le(code_view.guid()).simple(),
+            code_view.age()
+        ),
+        (None, None, None) => return Err(Error::ObjectHasNoUuid),
+    })
+}
+
+/// Calls `symbolicate` with the addresses of `ctx`, or with each line of addresses read from
+/// the standard input, flushing what it prints for a line before reading the next one.
+fn with_addrs(ctx: &Context, mut symbolicate: impl FnMut(Vec<Addr>) -> Result<()>) -> Result<()> {
+    match &ctx.addrs {
+        Addrs::List(addrs) => symbolicate(addrs.clone()),
+
+        Addrs::File(file) => symbolicate(report_invalid(
+            ctx.addr_input
+                .read(io::BufReader::new(fs::File::open(file)?)),
+        )),
+
+        Addrs::Stdin => {
+            for (index, line) in io::stdin().lock().lines().enumerate() {
+                let addrs = report_invalid(ctx.addr_input.parse_line(index + 1, &line?));
+                if !addrs.is_empty() {
+                    symbolicate(addrs)?;
+                    io::stdout().flush()?;
+                }
+            }
+
+            Ok(())
+        }
+    }
+}
 
-    fallible_iterator::convert(addrs.iter().map(|addr| {
-        addr.checked_add_signed(offset_addr)
-            .map(Addr::from)
-            .context(format!("Invalid address: {}", addr))
-    }))
-    .collect()
+/// The addresses read, printing the error of every word that isn't one to stderr.
+fn report_invalid(addrs: impl IntoIterator<Item = Result<Addr, Error>>) -> Vec<Addr> {
+    addrs
+        .into_iter()
+        .filter_map(|addr| addr.map_err(|err| eprintln!("{err}")).ok())
+        .collect()
 }
 
 trait LossyFileName {
@@ -0,0 +1,572 @@
+use crate::{
+    cli, context::Context, json, map_symcache, symbolicate_with, uuid_index, write_symcache,
+};
+use anyhow::{Context as _, Result};
+use atorsl::{
+    data::{Addr, Arch, Frames, Loc, Source, Symbolication},
+    ext::object::File as _,
+    *,
+};
+use itertools::Either;
+use memmap2::Mmap;
+use object::Object;
+use serde_json::{json, Value};
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{Arc, Condvar, Mutex, PoisonError},
+    thread,
+    time::Duration,
+};
+use uuid::Uuid;
+
+/// The largest body of an HTTP request, or line of a Unix socket, in bytes.
+const MAX_BODY_LEN: usize = 4 * 1024 * 1024;
+
+/// The largest request line and headers of an HTTP request, in bytes.
+const MAX_HEAD_LEN: u64 = 64 * 1024;
+
+/// How long a connection is waited on to send a request or read a response before it's closed,
+/// for idle clients to not hold on to the connections served at once.
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The most connections served at once, the ones after waiting to be accepted.
+const MAX_CONNECTIONS: usize = 64;
+
+/// A request to symbolicate addresses against a binary image.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Request {
+    /// The path to a binary image, .dSYM or Breakpad symbol file.
+    pub obj_path: Option<PathBuf>,
+
+    /// The UUID of the binary image, to find it in the search directories of the server, or
+    /// to select it in the .dSYM at `obj_path`.
+    pub uuid: Option<Uuid>,
+
+    /// The architecture of the binary image.
+    pub arch: Option<String>,
+
+    /// The location address of the binary image, offsets when neither a load address nor a
+    /// slide is requested.
+    pub base_addr: Loc,
+
+    /// The addresses to symbolicate.
+    pub addrs: Vec<Addr>,
+
+    /// Whether to expand inlined symbols.
+    pub include_inlined: bool,
+
+    /// Whether to return the full path of the source files.
+    pub show_full_path: bool,
+
+    /// Whether to return the column of source locations.
+    pub show_column: bool,
+}
+
+impl Request {
+    /// Reads the request in the JSON object `value`.
+    fn parse(value: &Value) -> Result<Self> {
+        let flag = |key: &str| value[key].as_bool().unwrap_or_default();
+
+        Ok(Self {
+            obj_path: value["image"].as_str().map(PathBuf::from),
+
+            uuid: value["uuid"]
+                .as_str()
+                .map(Uuid::parse_str)
+                .transpose()
+                .context("Invalid UUID")?,
+
+            arch: value["arch"].as_str().map(String::from),
+
+            base_addr: match (&value["load_address"], &value["slide"]) {
+                (Value::Null, Value::Null) => Loc::Offset,
+                (Value::Null, slide) => Loc::Slide(addr(slide).context("Invalid slide")?),
+                (load_addr, _) => Loc::Load(addr(load_addr).context("Invalid load address")?),
+            },
+
+            addrs: value["addresses"]
+                .as_array()
+                .context("No addresses")?
+                .iter()
+                .map(|value| addr(value).with_context(|| format!("Invalid address {value}")))
+                .collect::<Result<_>>()?,
+
+            include_inlined: flag("inline"),
+            show_full_path: flag("full_path"),
+            show_column: flag("column"),
+        })
+    }
+}
+
+/// An address given as a number, or as a string as on the command line.
+fn addr(value: &Value) -> Option<Addr> {
+    match value {
+        Value::Number(addr) => addr.as_u64().map(Addr::from),
+        Value::String(addr) => addr.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Serves the requests to the `serve` subcommand until it's killed.
+pub fn serve(args: &clap::ArgMatches) -> Result<()> {
+    let ctx = Context::from_serve_args(args);
+    let server = Server {
+        index: uuid_index(&ctx.search_dirs)?,
+        ctx,
+        images: Mutex::default(),
+        connections: Connections::new(MAX_CONNECTIONS),
+        max_memory: args
+            .get_one::<usize>(&cli::Opt::MaxMemory.to_string())
+            .copied()
+            .unwrap_or_default()
+            .saturating_mul(1024 * 1024),
+    };
+
+    match (
+        args.get_one::<PathBuf>(&cli::Opt::Socket.to_string()),
+        args.get_one::<SocketAddr>(&cli::Opt::Http.to_string()),
+    ) {
+        (Some(path), _) => server.serve_socket(path),
+        (None, Some(addr)) => server.serve_http(addr),
+        (None, None) => anyhow::bail!("No socket path or HTTP address to serve on"),
+    }
+}
+
+/// A symbolication server, keeping the binary images it loads for the requests after.
+struct Server<'a> {
+    /// The context every request's context is made from.
+    ctx: Context<'a>,
+
+    /// The binary images in the search directories, indexed once when the server starts.
+    index: UuidIndex,
+
+    /// The binary images loaded for the requests so far, the least recently used unloaded
+    /// first above `max_memory`.
+    images: Mutex<Images>,
+
+    /// The connections being served, at most [`MAX_CONNECTIONS`] at once.
+    connections: Connections,
+
+    /// The memory, in bytes, above which the least recently used images are unloaded.
+    max_memory: usize,
+}
+
+impl Server<'_> {
+    /// Serves requests on the Unix socket at `path`, a JSON object per line.
+    #[cfg(unix)]
+    fn serve_socket(&self, path: &Path) -> Result<()> {
+        use std::os::unix::{fs::FileTypeExt, net::UnixListener};
+
+        // A socket left by a previous server is replaced, any other file is kept.
+        if fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+            fs::remove_file(path)?;
+        }
+
+        let listener = UnixListener::bind(path)?;
+        println!("Listening on {}", path.display());
+
+        self.serve_connections(listener.incoming(), |stream| {
+            let _ = stream
+                .set_read_timeout(Some(TIMEOUT))
+                .and_then(|_| stream.set_write_timeout(Some(TIMEOUT)))
+                .and_then(|_| self.serve_lines(BufReader::new(&stream), &stream));
+        });
+
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn serve_socket(&self, _path: &Path) -> Result<()> {
+        anyhow::bail!("Unix sockets aren't supported on this platform")
+    }
+
+    /// Serves requests on `addr` over HTTP, the body of each POST a request.
+    fn serve_http(&self, addr: &SocketAddr) -> Result<()> {
+        let listener = TcpListener::bind(addr)?;
+        println!("Listening on http://{}", listener.local_addr()?);
+
+        self.serve_connections(listener.incoming(), |stream| {
+            let _ = stream
+                .set_read_timeout(Some(TIMEOUT))
+                .and_then(|_| stream.set_write_timeout(Some(TIMEOUT)))
+                .and_then(|_| self.serve_http_request(&stream));
+        });
+
+        Ok(())
+    }
+
+    /// Serves each connection accepted from `incoming` on a thread of its own, up to
+    /// [`MAX_CONNECTIONS`] at once.
+    ///
+    /// Errors accepting a connection are reported, and the next one is accepted.
+    fn serve_connections<S: Send>(
+        &self,
+        incoming: impl Iterator<Item = io::Result<S>>,
+        serve: impl Fn(S) + Sync,
+    ) {
+        let serve = &serve;
+        thread::scope(|scope| {
+            for stream in incoming {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        eprintln!("Cannot accept a connection: {err}");
+                        continue;
+                    }
+                };
+
+                let connection = self.connections.acquire();
+                scope.spawn(move || {
+                    serve(stream);
+                    drop(connection);
+                });
+            }
+        });
+    }
+
+    /// Responds to each line read from `reader` with a line written to `writer`, until a line
+    /// longer than [`MAX_BODY_LEN`].
+    fn serve_lines(&self, mut reader: impl BufRead, mut writer: impl Write) -> io::Result<()> {
+        let mut line = String::default();
+        loop {
+            line.clear();
+            match (&mut reader)
+                .take(MAX_BODY_LEN as u64 + 1)
+                .read_line(&mut line)?
+            {
+                0 => return Ok(()),
+                len if len > MAX_BODY_LEN => {
+                    let error = format!("Requests are at most {MAX_BODY_LEN} bytes");
+                    return writeln!(writer, "{}", json!({ "error": error }));
+                }
+                _ if line.trim().is_empty() => continue,
+                _ => (),
+            }
+
+            let response = self
+                .respond(&line)
+                .unwrap_or_else(|err| json!({ "error": err.to_string() }));
+
+            writeln!(writer, "{response}")?;
+        }
+    }
+
+    /// Responds to the HTTP request read from `stream`, closing it after.
+    fn serve_http_request(&self, mut stream: &TcpStream) -> io::Result<()> {
+        let mut reader = BufReader::new(stream);
+        let mut head = (&mut reader).take(MAX_HEAD_LEN);
+
+        let mut request_line = String::default();
+        head.read_line(&mut request_line)?;
+
+        let mut content_len = None;
+        loop {
+            let mut header = String::default();
+            if head.read_line(&mut header)? == 0 || header.trim_end().is_empty() {
+                break;
+            }
+
+            if let Some((name, value)) = header.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("content-length") {
+                    content_len = value.trim().parse::<usize>().ok();
+                }
+            }
+        }
+
+        let (status, response) = match content_len {
+            _ if head.limit() == 0 => (
+                "431 Request Header Fields Too Large",
+                json!({ "error": format!("Request headers are at most {MAX_HEAD_LEN} bytes") }),
+            ),
+
+            _ if !request_line.starts_with("POST ") => (
+                "405 Method Not Allowed",
+                json!({ "error": "Requests are POSTed" }),
+            ),
+
+            None => (
+                "411 Length Required",
+                json!({ "error": "Requests have a Content-Length" }),
+            ),
+
+            Some(content_len) if content_len > MAX_BODY_LEN => (
+                "413 Payload Too Large",
+                json!({ "error": format!("Requests are at most {MAX_BODY_LEN} bytes") }),
+            ),
+
+            Some(content_len) => {
+                let mut body = vec![0; content_len];
+                reader.read_exact(&mut body)?;
+
+                match self.respond(&String::from_utf8_lossy(&body)) {
+                    Ok(response) => ("200 OK", response),
+                    Err(err) => ("400 Bad Request", json!({ "error": err.to_string() })),
+                }
+            }
+        };
+
+        let response = response.to_string();
+        write!(
+            stream,
+            "HTTP/1.1 {status}\r\n\
+            Content-Type: application/json\r\n\
+            Content-Length: {}\r\n\
+            Connection: close\r\n\r\n\
+            {response}",
+            response.len()
+        )
+    }
+
+    /// Symbolicates the addresses of the JSON `request`.
+    fn respond(&self, request: &str) -> Result<Value> {
+        let request = Request::parse(&serde_json::from_str(request)?)?;
+        let ctx = self.ctx.with_request(&request, &self.index)?;
+        let image = self.image(&ctx)?;
+
+        // Symbols are returned as in the binary image a Breakpad symbol file was written from.
+        let ctx = Context {
+            obj_path: image.obj_path.clone(),
+            ..ctx
+        };
+
+        Ok(json!({
+            "symbolications": image
+                .symbolicate(&ctx, request.addrs.clone())?
+                .iter()
+                .map(|symbolication| json::symbolication(symbolication, &ctx))
+                .collect::<Value>(),
+        }))
+    }
+
+    /// The image of `ctx`, loaded unless it's been loaded already.
+    ///
+    /// Images are loaded outside the lock, for requests against loaded images not to wait on
+    /// it.
+    fn image(&self, ctx: &Context) -> Result<Arc<LoadedImage>> {
+        let key = (ctx.obj_path.clone(), ctx.arch);
+        if let Some(image) = self.images().get(&key) {
+            return Ok(image);
+        }
+
+        let image = Arc::new(LoadedImage::load(ctx)?);
+        self.images()
+            .insert(key, image.clone(), self.max_memory);
+
+        Ok(image)
+    }
+
+    fn images(&self) -> std::sync::MutexGuard<'_, Images> {
+        self.images
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// The connections a [`Server`] is serving, counted to limit them.
+struct Connections {
+    count: Mutex<usize>,
+    released: Condvar,
+    max: usize,
+}
+
+/// A connection counted by [`Connections`] until it's dropped.
+struct Connection<'a>(&'a Connections);
+
+impl Connections {
+    fn new(max: usize) -> Self {
+        Self {
+            count: Mutex::default(),
+            released: Condvar::new(),
+            max,
+        }
+    }
+
+    /// Counts a connection, waiting for one to be released while there are `max` already.
+    fn acquire(&self) -> Connection<'_> {
+        let mut count = self
+            .released
+            .wait_while(
+                self.count
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner),
+                |count| *count >= self.max,
+            )
+            .unwrap_or_else(PoisonError::into_inner);
+
+        *count += 1;
+        Connection(self)
+    }
+}
+
+impl Drop for Connection<'_> {
+    fn drop(&mut self) {
+        *self
+            .0
+            .count
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) -= 1;
+        self.0.released.notify_one();
+    }
+}
+
+/// The path and architecture of a binary image loaded by a [`Server`].
+type ImageKey = (PathBuf, Option<Arch>);
+
+/// The loaded images of a [`Server`], least recently used first.
+#[derive(Default)]
+struct Images {
+    images: Vec<(ImageKey, Arc<LoadedImage>)>,
+    size: usize,
+}
+
+impl Images {
+    fn get(&mut self, key: &ImageKey) -> Option<Arc<LoadedImage>> {
+        let index = self
+            .images
+            .iter()
+            .position(|(image_key, _)| image_key == key)?;
+        let entry = self.images.remove(index);
+        let image = entry.1.clone();
+        self.images.push(entry);
+
+        Some(image)
+    }
+
+    /// Inserts `image`, unloading the least recently used images for their size to stay
+    /// under `max_memory`, but for the one inserted.
+    fn insert(&mut self, key: ImageKey, image: Arc<LoadedImage>, max_memory: usize) {
+        if self.get(&key).is_some() {
+            return;
+        }
+
+        self.size += image.size;
+        self.images.push((key, image));
+
+        while self.size > max_memory && self.images.len() > 1 {
+            let (_, image) = self.images.remove(0);
+            self.size -= image.size;
+        }
+    }
+}
+
+/// A binary image loaded by a [`Server`], or to symbolicate addresses across several.
+pub struct LoadedImage {
+    /// The path of the binary image, as symbols are returned in.
+    pub obj_path: PathBuf,
+    pub vmaddr: Option<Addr>,
+
+    /// The address range of its text, as built, unless it has no segments.
+    pub text: Option<Range<u64>>,
+
+    symbols: Symbols,
+
+    /// The memory taken by the symbols, in bytes.
+    size: usize,
+}
+
+/// The symbols of a [`LoadedImage`].
+enum Symbols {
+    /// The symbol cache of a binary image, mapped from the cache directory or kept in memory,
+    /// along with its PDB.
+    SymCache(Either<Mmap, Vec<u8>>, Option<PdbFile>),
+
+    /// A Breakpad symbol file.
+    Breakpad(BreakpadSym),
+}
+
+impl LoadedImage {
+    /// Loads the binary image of `ctx`, with its debug info indexed into a symbol cache kept in
+    /// memory, or in the cache directory of `ctx`.
+    pub fn load(ctx: &Context) -> Result<Self> {
+        if BreakpadSym::is_sym(&ctx.obj_path) {
+            let sym = BreakpadSym::parse(&fs::read_to_string(&ctx.obj_path)?)?;
+
+            return Ok(Self {
+                obj_path: PathBuf::from(&sym.module.name),
+                vmaddr: Some(sym.base_addr),
+                text: Some(sym.addr_range()),
+                size: sym.memory_size(),
+                symbols: Symbols::Breakpad(sym),
+            });
+        }
+
+        let mmap = unsafe { Mmap::map(&fs::File::open(&ctx.obj_path)?) }?;
+        let obj = object::File::parse_data(&mmap, ctx.arch)?;
+        let text = obj.text_range().ok();
+
+        let debug_path = if obj.has_debug_symbols() {
+            None
+        } else {
+            debug_file::find(&obj, &ctx.obj_path, &ctx.debug_dirs)?
+        };
+
+        let debug_mmap;
+        let obj = match debug_path {
+            Some(debug_path) => {
+                debug_mmap = unsafe { Mmap::map(&fs::File::open(debug_path)?) }?;
+                object::File::parse_data(&debug_mmap, ctx.arch)?
+            }
+            None => obj,
+        };
+
+        let pdb_file = PdbFile::find(&obj, &ctx.obj_path, &ctx.debug_dirs)?
+            .map(|pdb_path| PdbFile::load(&obj, &pdb_path))
+            .transpose()?;
+
+        // The symbol cache covers what the PDB doesn't, as the DWARF does for an `Image`.
+        let symcache = match ctx.cache_dir.zip(SymCache::file_name(&obj)?) {
+            Some((cache_dir, file_name)) => {
+                Either::Left(map_symcache(&cache_dir.join(file_name), &obj, ctx)?)
+            }
+            None => {
+                let mut data = Vec::default();
+                write_symcache(&obj, ctx, &mut data)?;
+                Either::Right(data)
+            }
+        };
+
+        // The cache is checked once, as reading it is only checking its header.
+        SymCache::parse(&symcache)?;
+
+        // A PDB is read whole when loaded, so it's counted whole, however few of its functions
+        // are looked up.
+        let pdb_size = pdb_file.as_ref().map_or(0, PdbFile::memory_size);
+
+        Ok(Self {
+            obj_path: ctx.obj_path.clone(),
+            vmaddr: obj.vmaddr().ok(),
+            text,
+            size: symcache.len() + pdb_size,
+            symbols: Symbols::SymCache(symcache, pdb_file),
+        })
+    }
+
+    /// Symbolicates `addrs`, as `ators` does with the options of `ctx`.
+    pub fn symbolicate(&self, ctx: &Context, addrs: Vec<Addr>) -> Result<Vec<Symbolication>> {
+        match &self.symbols {
+            Symbols::SymCache(symcache, pdb_file) => {
+                let symcache = SymCache::parse(symcache)?;
+                symbolicate_with(ctx, addrs, self.vmaddr, |addr| {
+                    match pdb_file
+                        .as_ref()
+                        .map(|pdb_file| pdb_file.atos(addr, ctx.include_inlined))
+                    {
+                        Some(Ok(symbols)) => Ok(Frames { source: Source::Pdb, symbols }),
+                        Some(Err(Error::AddrNotFound(_))) | None => {
+                            symcache.atos(addr, ctx.include_inlined)
+                        }
+                        Some(Err(err)) => Err(err),
+                    }
+                })
+            }
+
+            Symbols::Breakpad(sym) => symbolicate_with(ctx, addrs, self.vmaddr, |addr| {
+                sym.atos(addr, ctx.include_inlined)
+            }),
+        }
+    }
+}
@@ -0,0 +1,24 @@
+mod common;
+use common::{ators_lines, fixture};
+
+#[test]
+fn test_column() {
+    assert_eq!(
+        ators_lines(&[
+            "--column",
+            "-i",
+            "-o",
+            &fixture("inlined_elf").to_string_lossy(),
+            "-s",
+            "0",
+            "--",
+            "0x401196",
+        ]),
+        [
+            "square (in inlined_elf) (inlined.c:5:44)",
+            "sum_of_squares (in inlined_elf) (inlined.c:7:57)",
+            "compute (in inlined_elf) (inlined.c:12:16)",
+            "",
+        ]
+    );
+}
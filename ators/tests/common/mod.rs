@@ -0,0 +1,31 @@
+#![allow(dead_code)]
+
+use anyhow::Result;
+use std::{env, path::PathBuf, process::Command};
+
+pub fn path(file: &str) -> Result<PathBuf> {
+    Ok([&env::var("CARGO_MANIFEST_DIR")?, "..", "fixtures", file]
+        .iter()
+        .collect())
+}
+
+/// The path to the object `name` in the fixtures.
+pub fn fixture(name: &str) -> PathBuf {
+    path("objects").expect("fixtures to exist").join(name)
+}
+
+/// Runs `ators` with `args`, expecting it to succeed, and returns what it printed.
+pub fn ators(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_ators"))
+        .args(args)
+        .output()
+        .expect("ators to run");
+
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).expect("ators output to be utf8")
+}
+
+/// Runs `ators` with `args`, expecting it to succeed, and returns the lines it printed.
+pub fn ators_lines(args: &[&str]) -> Vec<String> {
+    ators(args).lines().map(String::from).collect()
+}
@@ -0,0 +1,78 @@
+use serde_json::Value;
+
+mod common;
+use common::{ators, fixture, path};
+
+fn ators_crash_report(name: &str) -> String {
+    ators(&[
+        "-i",
+        "-o",
+        &fixture("inlined_macho.dSYM").to_string_lossy(),
+        "--crash-report",
+        &path("crash_reports")
+            .expect("crash reports to exist")
+            .join(name)
+            .to_string_lossy(),
+    ])
+}
+
+#[test]
+fn test_crash_report_text() {
+    let output = ators_crash_report("inlined_macho.crash");
+    let lines = output.lines().collect::<Vec<_>>();
+
+    let thread = lines
+        .iter()
+        .position(|line| *line == "Thread 0 Crashed:")
+        .expect("crash report to have a crashed thread");
+
+    assert_eq!(
+        lines[thread + 1..thread + 7],
+        [
+            "0   inlined_macho                 \t0x0000000104a982b4 <i32>::wrapping_mul (int_macros.rs:2173)",
+            "0   inlined_macho                 \t0x0000000104a982b4 inlined_macho::square::h5a8e55dc13488735 (inlined_macho.rs:12)",
+            "0   inlined_macho                 \t0x0000000104a982b4 inlined_macho::sum_of_squares::h7a4f856cf6ee070d (inlined_macho.rs:17)",
+            "0   inlined_macho                 \t0x0000000104a982b4 compute (inlined_macho.rs:23)",
+            "1   inlined_macho                 \t0x0000000104a982d0 main (inlined_macho.rs:27)",
+            "2   dyld                          \t0x00007ff80a5d441f start + 1903",
+        ]
+    );
+}
+
+#[test]
+fn test_crash_report_ips() {
+    let output = ators_crash_report("inlined_macho.ips");
+    let (header, body) = output.split_once('\n').expect("ips to have a header");
+
+    assert!(serde_json::from_str::<Value>(header).is_ok());
+
+    let body = serde_json::from_str::<Value>(body).expect("ips body to be JSON");
+    let frames = body["threads"][0]["frames"]
+        .as_array()
+        .expect("thread to have frames")
+        .iter()
+        .map(|frame| {
+            (
+                frame["symbol"].as_str().unwrap_or_default(),
+                frame["sourceLine"].as_u64(),
+                frame.get("inline").is_some(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        frames,
+        [
+            ("<i32>::wrapping_mul", Some(2173), true),
+            ("inlined_macho::square::h5a8e55dc13488735", Some(12), true),
+            (
+                "inlined_macho::sum_of_squares::h7a4f856cf6ee070d",
+                Some(17),
+                true
+            ),
+            ("compute", Some(23), false),
+            ("main", Some(27), false),
+            ("start", None, false),
+        ]
+    );
+}
@@ -0,0 +1,66 @@
+mod common;
+use common::{ators_lines, fixture, path};
+
+#[test]
+fn test_build_id() {
+    let obj_path = fixture("inlined_stripped_build_id");
+
+    assert_eq!(
+        ators_lines(&["--uuid", "-o", &obj_path.to_string_lossy()]),
+        [format!(
+            "    a1423422d5e4819d6389557b294103529a6fc66a x86_64   {}",
+            obj_path.to_string_lossy()
+        )]
+    );
+}
+
+#[test]
+fn test_debug_dir() {
+    assert_eq!(
+        ators_lines(&[
+            "-o",
+            &fixture("inlined_stripped_build_id").to_string_lossy(),
+            "--debug-dir",
+            &path("debug")
+                .expect("debug dir to exist")
+                .to_string_lossy(),
+            "-s",
+            "0",
+            "--",
+            "0x401196",
+        ]),
+        ["compute (in inlined_stripped_build_id) (inlined.c:5)"]
+    );
+}
+
+#[test]
+fn test_pdb() {
+    let obj_path = fixture("inlined_pe.exe");
+
+    assert_eq!(
+        ators_lines(&["--uuid", "-o", &obj_path.to_string_lossy()]),
+        [format!(
+            "    DEF0FF390329A1EF4C4C44205044422E1 x86_64   {}",
+            obj_path.to_string_lossy()
+        )]
+    );
+
+    assert_eq!(
+        ators_lines(&[
+            "-i",
+            "-o",
+            &obj_path.to_string_lossy(),
+            "-l",
+            "0x140000000",
+            "--",
+            "0x140001010",
+        ]),
+        [
+            "core::num::impl$2::wrapping_mul (in inlined_pe.exe) (int_macros.rs:2173)",
+            "inlined_pe::square (in inlined_pe.exe) (inlined_pe.rs:9)",
+            "inlined_pe::sum_of_squares (in inlined_pe.exe) (inlined_pe.rs:14)",
+            "inlined_pe::compute (in inlined_pe.exe) (inlined_pe.rs:20)",
+            "",
+        ]
+    );
+}
@@ -0,0 +1,60 @@
+use std::process::Command;
+
+mod common;
+use common::{ators_lines, fixture};
+
+#[test]
+fn test_dump_syms() {
+    let sym = ators_lines(&[
+        "dump-syms",
+        &fixture("inlined_macho.dSYM").to_string_lossy(),
+    ]);
+
+    assert_eq!(
+        sym[0],
+        "MODULE mac x86_64 4C4C44CA55553144A1471FD6514C28820 inlined_macho"
+    );
+    assert!(sym.contains(&String::from("FUNC 2b0 11 0 compute")));
+    assert!(sym.contains(&String::from("INLINE 0 23 0 0 2b4 8")));
+}
+
+#[test]
+fn test_breakpad_sym() {
+    let sym = fixture("inlined_elf.sym");
+    let sym_args = ["-i", "-o", &sym.to_string_lossy()];
+
+    for args in [
+        &["-l", "0x7f0000", "--", "0x7f1196"][..],
+        &["-s", "0", "--", "0x401196"],
+        &["-s", "0x1000", "--", "0x402196"],
+        &["--offset", "--", "0x1196"],
+    ] {
+        assert_eq!(
+            ators_lines(&[&sym_args[..], args].concat()),
+            [
+                "square (in inlined_elf) (inlined.c:5)",
+                "sum_of_squares (in inlined_elf) (inlined.c:7)",
+                "compute (in inlined_elf) (inlined.c:12)",
+                "",
+            ]
+        );
+    }
+}
+
+#[test]
+fn test_breakpad_sym_lookup() {
+    let output = Command::new(env!("CARGO_BIN_EXE_ators"))
+        .args([
+            "-o",
+            &fixture("inlined_elf.sym").to_string_lossy(),
+            "-l",
+            "0x7f0000",
+            "--lookup-symbol",
+            "compute",
+        ])
+        .output()
+        .expect("ators to run");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Breakpad symbol files"));
+}
@@ -0,0 +1,52 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+mod common;
+use common::fixture;
+
+#[test]
+fn test_filter() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ators"))
+        .args([
+            "-i",
+            "-o",
+            &fixture("inlined_elf").to_string_lossy(),
+            "-s",
+            "0x2000",
+            "--filter",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("ators to run");
+
+    child
+        .stdin
+        .take()
+        .expect("ators stdin")
+        .write_all(
+            b"Thread 0:\n\
+            ./inlined_elf(compute+0x6) [0x403196]\n\
+            ./inlined_elf_old(compute+0x6) [0x403196]\n\
+            \x20  3: 0x403196 - <unknown>\n\
+            /lib/libc.so.6(__libc_start_main+0xf3) [0x7f0000001234]\n",
+        )
+        .expect("backtrace to be written");
+
+    let output = child.wait_with_output().expect("ators to exit");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Thread 0:\n\
+        ./inlined_elf(square (inlined.c:5)) [0x403196]\n\
+        ./inlined_elf(sum_of_squares (inlined.c:7)) [0x403196]\n\
+        ./inlined_elf(compute (inlined.c:12)) [0x403196]\n\
+        ./inlined_elf_old(compute+0x6) [0x403196]\n\
+        \x20  3: 0x403196 - square (inlined.c:5)\n\
+        \x20  3: 0x403196 - sum_of_squares (inlined.c:7)\n\
+        \x20  3: 0x403196 - compute (inlined.c:12)\n\
+        /lib/libc.so.6(__libc_start_main+0xf3) [0x7f0000001234]\n"
+    );
+}
@@ -0,0 +1,100 @@
+use std::{env, fs, process::Command};
+
+mod common;
+use common::{ators, fixture};
+
+#[test]
+fn test_images() {
+    let binary = format!("{}@0x105000000", fixture("inlined_macho").to_string_lossy());
+    let dsym = format!(
+        "{}@0x200000000",
+        fixture("inlined_macho.dSYM").to_string_lossy()
+    );
+
+    assert_eq!(
+        ators(&[
+            "-o",
+            &binary,
+            "-o",
+            &dsym,
+            "--",
+            "0x1050002d0",
+            "0x2000002b0",
+            "0x300000000",
+        ]),
+        "main (in inlined_macho) (inlined_macho.rs:27)\n\
+        compute (in inlined_macho) (inlined_macho.rs:22)\n\
+        Address is in no binary image: 0x0000000300000000\n"
+    );
+}
+
+#[test]
+fn test_images_file() {
+    let images = env::temp_dir().join(format!("ators-images-{}.txt", std::process::id()));
+    fs::write(
+        &images,
+        format!(
+            "# Binary images\n\n{}@0x105000000\n",
+            fixture("inlined_macho.dSYM").to_string_lossy()
+        ),
+    )
+    .expect("images file to be written");
+
+    let output = ators(&["--images", &images.to_string_lossy(), "--", "0x1050002b0"]);
+    fs::remove_file(&images).ok();
+
+    assert_eq!(output, "compute (in inlined_macho) (inlined_macho.rs:22)\n");
+}
+
+#[test]
+fn test_images_elf() {
+    let elf = format!(
+        "{}@0x7f0000000000",
+        fixture("inlined_elf").to_string_lossy()
+    );
+    let sym = format!(
+        "{}@0x7f0001000000",
+        fixture("inlined_elf.sym").to_string_lossy()
+    );
+
+    assert_eq!(
+        ators(&[
+            "-i",
+            "-o",
+            &elf,
+            "-o",
+            &sym,
+            "--",
+            "0x7f0000001196",
+            "0x7f0001001196",
+        ]),
+        "square (in inlined_elf) (inlined.c:5)\n\
+        sum_of_squares (in inlined_elf) (inlined.c:7)\n\
+        compute (in inlined_elf) (inlined.c:12)\n\
+        \n\
+        square (in inlined_elf) (inlined.c:5)\n\
+        sum_of_squares (in inlined_elf) (inlined.c:7)\n\
+        compute (in inlined_elf) (inlined.c:12)\n\
+        \n"
+    );
+}
+
+#[test]
+fn test_images_without_load_addr() {
+    let output = Command::new(env!("CARGO_BIN_EXE_ators"))
+        .args([
+            "-o",
+            &fixture("inlined_elf").to_string_lossy(),
+            "-o",
+            &fixture("inlined_elf.sym").to_string_lossy(),
+            "-s",
+            "0",
+            "--",
+            "0x401196",
+        ])
+        .output()
+        .expect("ators to run");
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+}
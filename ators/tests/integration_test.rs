@@ -1,23 +1,18 @@
-use anyhow::Result;
 use pretty_assertions::assert_str_eq;
 use std::{
-    env, fs,
+    fs,
     io::{self, BufRead},
-    path::PathBuf,
     process::Command,
     str,
 };
 
+mod common;
+use common::path;
+
 pub const TEST_DWARF: &str = "test.dwarf";
 pub const TEST_ADDRS: &str = "test_addrs.txt";
 pub const TEST_SYMR: &str = "test_symr.txt";
 
-pub fn path(file: &str) -> Result<PathBuf> {
-    Ok([&env::var("CARGO_MANIFEST_DIR")?, "..", "fixtures", file]
-        .iter()
-        .collect())
-}
-
 #[test]
 fn test() {
     let output = Command::new("../target/debug/ators")
@@ -0,0 +1,96 @@
+use serde_json::{json, Value};
+
+mod common;
+use common::{ators, fixture};
+
+#[test]
+fn test_json() {
+    let obj = fixture("inlined_elf");
+    let output = ators(&[
+        "--format",
+        "json",
+        "-i",
+        "-o",
+        &obj.to_string_lossy(),
+        "-s",
+        "0x1000",
+        "--",
+        "0x402196",
+        "0x402090",
+        "0x10",
+    ]);
+
+    let value = serde_json::from_str::<Value>(&output).expect("ators output to be json");
+
+    assert_eq!(
+        value[0],
+        json!({
+            "input_address": "0x402196",
+            "address": "0x401196",
+            "image": "inlined_elf",
+            "frames": [
+                {
+                    "symbol": "square",
+                    "mangled_name": null,
+                    "file": "inlined.c",
+                    "line": 5,
+                    "column": 44,
+                    "offset": null,
+                    "inlined": true,
+                    "source": "dwarf",
+                },
+                {
+                    "symbol": "sum_of_squares",
+                    "mangled_name": null,
+                    "file": "inlined.c",
+                    "line": 7,
+                    "column": 57,
+                    "offset": null,
+                    "inlined": true,
+                    "source": "dwarf",
+                },
+                {
+                    "symbol": "compute",
+                    "mangled_name": null,
+                    "file": "inlined.c",
+                    "line": 12,
+                    "column": 16,
+                    "offset": null,
+                    "inlined": false,
+                    "source": "dwarf",
+                },
+            ],
+        })
+    );
+
+    assert_eq!(value[1]["frames"][0]["source"], "symtab");
+    assert_eq!(value[1]["frames"][0]["offset"], 0);
+
+    assert_eq!(value[2]["address"], Value::Null);
+    assert_eq!(value[2]["frames"], json!([]));
+    assert!(value[2]["error"].is_string());
+}
+
+#[test]
+fn test_ndjson() {
+    let obj = fixture("inlined_elf");
+    let output = ators(&[
+        "--format",
+        "ndjson",
+        "-o",
+        &obj.to_string_lossy(),
+        "-s",
+        "0",
+        "--",
+        "0x401196",
+        "0x401050",
+    ]);
+
+    let symbols = output
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).expect("ators output line to be json"))
+        .map(|value| value["frames"][0]["symbol"].clone())
+        .collect::<Vec<_>>();
+
+    assert_eq!(symbols, [json!("compute"), json!("main")]);
+}
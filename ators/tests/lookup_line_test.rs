@@ -0,0 +1,21 @@
+mod common;
+use common::{ators, fixture};
+
+#[test]
+fn test_lookup_line() {
+    assert_eq!(
+        ators(&[
+            "-o",
+            &fixture("inlined_macho.dSYM").to_string_lossy(),
+            "-l",
+            "0x105000000",
+            "--column",
+            "--lookup-line",
+            "int_macros.rs:2101",
+        ]),
+        "0x00000001050002ba-0x00000001050002bc int_macros.rs:2101:13\n    \
+        <i32>::wrapping_add (in inlined_macho) (int_macros.rs:2101:13)\n    \
+        inlined_macho::sum_of_squares::h7a4f856cf6ee070d (in inlined_macho) (inlined_macho.rs:17:15)\n    \
+        compute (in inlined_macho) (inlined_macho.rs:23:5)\n"
+    );
+}
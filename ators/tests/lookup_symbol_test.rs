@@ -0,0 +1,31 @@
+mod common;
+use common::{ators, fixture};
+
+fn lookup_symbol(query: &str) -> String {
+    ators(&[
+        "-o",
+        &fixture("inlined_macho.dSYM").to_string_lossy(),
+        "-l",
+        "0x105000000",
+        "--lookup-symbol",
+        query,
+    ])
+}
+
+#[test]
+fn test_lookup_symbol() {
+    assert_eq!(
+        lookup_symbol("<i32>::wrapping_add"),
+        "0x00000001050002ba-0x00000001050002bc <i32>::wrapping_add (in inlined_macho) \
+        (inlined into inlined_macho::sum_of_squares::h7a4f856cf6ee070d at inlined_macho.rs:17)\n"
+    );
+
+    assert_eq!(
+        lookup_symbol("compute|main"),
+        "0x00000001050002b0-0x00000001050002c1 compute (in inlined_macho)\n\
+        0x00000001050002d0-0x00000001050002e4 main (in inlined_macho)\n"
+    );
+
+    // Names of Objective-C methods start with a hyphen, and aren't options.
+    assert_eq!(lookup_symbol("-[SKTGraphicView drawRect:]"), "");
+}
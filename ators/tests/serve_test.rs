@@ -0,0 +1,180 @@
+use serde_json::{json, Value};
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpStream,
+    process::{Child, Command, Stdio},
+};
+
+mod common;
+use common::fixture;
+
+fn ators(args: &[&str]) -> Value {
+    serde_json::from_str(&common::ators(args)).expect("ators output to be json")
+}
+
+/// A running `ators serve`, killed when dropped.
+struct Server(Child);
+
+impl Server {
+    /// Starts `ators serve` with `args`, returning it along with where it's listening.
+    fn start(args: &[&str]) -> (Self, String) {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_ators"))
+            .arg("serve")
+            .args(args)
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("ators serve to run");
+
+        let mut line = String::default();
+        BufReader::new(child.stdout.as_mut().expect("ators serve stdout"))
+            .read_line(&mut line)
+            .expect("ators serve to be listening");
+
+        let addr = line
+            .trim()
+            .strip_prefix("Listening on ")
+            .expect("ators serve to print where it's listening")
+            .to_string();
+
+        (Self(child), addr)
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// Sends the raw HTTP `request` to the server at `addr`, returning its raw response.
+fn http(addr: &str, request: &str) -> String {
+    let mut stream =
+        TcpStream::connect(addr.trim_start_matches("http://")).expect("server to accept");
+    stream
+        .write_all(request.as_bytes())
+        .expect("request to be written");
+
+    let mut response = String::default();
+    stream
+        .read_to_string(&mut response)
+        .expect("response to be read");
+
+    response
+}
+
+#[cfg(unix)]
+#[test]
+fn test_serve_socket() {
+    use std::os::unix::net::UnixStream;
+
+    let socket =
+        std::env::temp_dir().join(format!("ators_test_serve_{}.sock", std::process::id()));
+    let (_server, addr) = Server::start(&["--socket", &socket.to_string_lossy()]);
+
+    let stream = UnixStream::connect(addr).expect("socket to connect");
+    let mut reader = BufReader::new(&stream);
+    let mut request = |request: Value| {
+        writeln!(&stream, "{request}").expect("request to be written");
+        let mut response = String::default();
+        reader
+            .read_line(&mut response)
+            .expect("response to be read");
+        serde_json::from_str::<Value>(&response).expect("response to be json")
+    };
+
+    let obj = fixture("inlined_elf");
+    let expected = ators(&[
+        "--format",
+        "json",
+        "-i",
+        "-o",
+        &obj.to_string_lossy(),
+        "-s",
+        "0x1000",
+        "--",
+        "0x402196",
+        "0x10",
+    ]);
+
+    // The second request is served by the image loaded for the first.
+    for _ in 0..2 {
+        let response = request(json!({
+            "image": obj,
+            "slide": "0x1000",
+            "addresses": ["0x402196", 16],
+            "inline": true,
+        }));
+
+        assert_eq!(response["symbolications"], expected);
+    }
+
+    let response = request(json!({ "image": fixture("inlined_elf.sym"), "addresses": [0x1196] }));
+    assert_eq!(
+        response["symbolications"][0]["frames"][0]["source"],
+        "breakpad"
+    );
+
+    let response = request(json!({ "addresses": [] }));
+    assert_eq!(response["error"], "No binary image path or UUID");
+}
+
+#[test]
+fn test_serve_http() {
+    let (_server, addr) = Server::start(&["--http", "127.0.0.1:0"]);
+
+    let obj = fixture("inlined_macho.dSYM");
+    let body = json!({ "image": obj, "addresses": ["0x2ba"], "inline": true }).to_string();
+
+    let response = http(
+        &addr,
+        &format!(
+            "POST / HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        ),
+    );
+
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .expect("response to have a body");
+    assert!(head.starts_with("HTTP/1.1 200 OK"));
+
+    let expected = ators(&[
+        "--format",
+        "json",
+        "-i",
+        "-o",
+        &obj.to_string_lossy(),
+        "--offset",
+        "--",
+        "0x2ba",
+    ]);
+
+    assert_eq!(
+        serde_json::from_str::<Value>(body).expect("response to be json")["symbolications"],
+        expected
+    );
+}
+
+#[test]
+fn test_serve_http_content_length() {
+    let (_server, addr) = Server::start(&["--http", "127.0.0.1:0"]);
+
+    assert!(http(&addr, "POST / HTTP/1.1\r\n\r\n{}").starts_with("HTTP/1.1 411 Length Required"));
+    assert!(http(
+        &addr,
+        "POST / HTTP/1.1\r\nContent-Length: 1000000000\r\n\r\n"
+    )
+    .starts_with("HTTP/1.1 413 Payload Too Large"));
+}
+
+#[test]
+fn test_serve_http_headers_too_large() {
+    let (_server, addr) = Server::start(&["--http", "127.0.0.1:0"]);
+
+    // A header line that never ends, up to the most the server reads of them.
+    let head = "POST / HTTP/1.1\r\nX-Padding: ";
+    let request = format!("{head}{}", "a".repeat(64 * 1024 - head.len()));
+
+    assert!(http(&addr, &request).starts_with("HTTP/1.1 431 Request Header Fields Too Large"));
+}
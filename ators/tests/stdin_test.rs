@@ -0,0 +1,47 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Command, Stdio},
+};
+
+mod common;
+use common::fixture;
+
+#[test]
+fn test_stdin() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ators"))
+        .args([
+            "-i",
+            "-o",
+            &fixture("inlined_elf").to_string_lossy(),
+            "-s",
+            "0x1000",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("ators to run");
+
+    let mut stdin = child.stdin.take().expect("ators stdin");
+    let mut stdout = BufReader::new(child.stdout.take().expect("ators stdout")).lines();
+    let mut read_line = || {
+        stdout
+            .next()
+            .expect("ators to print a line")
+            .expect("ators output to be utf8")
+    };
+
+    // Each line is symbolicated before the next one is written.
+    writeln!(stdin, "0x402196").expect("address to be written");
+    assert_eq!(read_line(), "square (in inlined_elf) (inlined.c:5)");
+    assert_eq!(read_line(), "sum_of_squares (in inlined_elf) (inlined.c:7)");
+    assert_eq!(read_line(), "compute (in inlined_elf) (inlined.c:12)");
+    assert_eq!(read_line(), "");
+
+    writeln!(stdin, "\n0x402090 0x402196").expect("addresses to be written");
+    assert_eq!(read_line(), "start (in inlined_elf) + 0");
+    assert_eq!(read_line(), "");
+    assert_eq!(read_line(), "square (in inlined_elf) (inlined.c:5)");
+
+    drop(stdin);
+    assert!(child.wait().expect("ators to exit").success());
+}
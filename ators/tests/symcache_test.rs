@@ -0,0 +1,46 @@
+use std::fs;
+
+mod common;
+use common::{ators_lines, fixture};
+
+#[test]
+fn test_cache_dir() {
+    let cache_dir =
+        std::env::temp_dir().join(format!("ators_test_cache_dir_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&cache_dir);
+
+    let obj_path = fixture("inlined_macho.dSYM");
+    let args = [
+        "-i",
+        "-o",
+        &obj_path.to_string_lossy(),
+        "--offset",
+        "--",
+        "0x2ba",
+    ];
+
+    let symbols = [
+        "<i32>::wrapping_add (in inlined_macho) (int_macros.rs:2101)",
+        "inlined_macho::sum_of_squares::h7a4f856cf6ee070d (in inlined_macho) (inlined_macho.rs:17)",
+        "compute (in inlined_macho) (inlined_macho.rs:23)",
+        "",
+    ];
+
+    assert_eq!(ators_lines(&args), symbols);
+
+    let cache_dir_arg = cache_dir.to_string_lossy();
+    let cached_args = [&["--cache-dir", &cache_dir_arg], &args[..]].concat();
+    assert_eq!(ators_lines(&cached_args), symbols);
+    let symcache_path = cache_dir.join("4c4c44ca55553144a1471fd6514c2882-x86_64.symcache");
+    assert!(symcache_path.is_file());
+
+    assert_eq!(ators_lines(&cached_args), symbols);
+
+    // A cache whose records point past its end, here the inlined functions of `compute`, its
+    // second function, is written again.
+    let mut symcache = fs::read(&symcache_path).expect("cache to be written");
+    symcache[116..120].copy_from_slice(&u32::MAX.to_le_bytes());
+    fs::write(&symcache_path, symcache).expect("cache to be writable");
+
+    assert_eq!(ators_lines(&cached_args), symbols);
+}
@@ -0,0 +1,79 @@
+use std::process::Command;
+
+mod common;
+use common::{fixture, path};
+
+fn fixtures() -> String {
+    path("")
+        .expect("fixtures to exist")
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[test]
+fn test_uuid_lookup() {
+    let output = Command::new(env!("CARGO_BIN_EXE_ators"))
+        .args([
+            "-i",
+            "--uuid-lookup",
+            "4C4C44CA-5555-3144-A147-1FD6514C2882",
+            "--search",
+            &fixtures(),
+            "-l",
+            "0x104a98000",
+            "--",
+            "0x104a982b4",
+        ])
+        .output()
+        .expect("ators to run");
+
+    assert_eq!(
+        String::from_utf8(output.stdout)
+            .expect("ators output to be utf8")
+            .lines()
+            .collect::<Vec<_>>(),
+        [
+            "<i32>::wrapping_mul (in inlined_macho) (int_macros.rs:2173)",
+            "inlined_macho::square::h5a8e55dc13488735 (in inlined_macho) (inlined_macho.rs:12)",
+            "inlined_macho::sum_of_squares::h7a4f856cf6ee070d (in inlined_macho) (inlined_macho.rs:17)",
+            "compute (in inlined_macho) (inlined_macho.rs:23)",
+            "",
+        ]
+    );
+}
+
+#[test]
+fn test_uuid_lookup_not_found() {
+    let output = Command::new(env!("CARGO_BIN_EXE_ators"))
+        .args([
+            "--uuid",
+            "--uuid-lookup",
+            "00000000-0000-0000-0000-000000000000",
+            "--search",
+            &fixtures(),
+        ])
+        .output()
+        .expect("ators to run");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_uuid_lookup_not_dsym() {
+    let output = Command::new(env!("CARGO_BIN_EXE_ators"))
+        .args([
+            "--uuid-lookup",
+            "4C4C44CA-5555-3144-A147-1FD6514C2882",
+            "-o",
+            &fixture("inlined_macho").to_string_lossy(),
+            "-l",
+            "0x104a98000",
+            "--",
+            "0x104a982b4",
+        ])
+        .output()
+        .expect("ators to run");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("can only select a DWARF file"));
+}
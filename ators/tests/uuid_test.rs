@@ -0,0 +1,87 @@
+use std::process::Command;
+
+mod common;
+use common::{ators_lines, fixture};
+
+#[test]
+fn test_uuid_dsym_dwarf_files() {
+    let dsym = fixture("inlined_macho.framework.dSYM");
+    let elf = fixture("inlined_elf");
+    let dwarf = dsym.join("Contents/Resources/DWARF");
+
+    assert_eq!(
+        ators_lines(&[
+            "--uuid",
+            "-o",
+            &dsym.to_string_lossy(),
+            "-o",
+            &elf.to_string_lossy(),
+        ]),
+        [
+            format!(
+                "    4C4C44CA-5555-3144-A147-1FD6514C2882 x86_64   {}",
+                dwarf.join("inlined_macho").to_string_lossy()
+            ),
+            format!(
+                "    4C4C44F1-5555-3144-A199-1876083EA918 x86_64   {}",
+                dwarf.join("inlined_macho_archive").to_string_lossy()
+            ),
+            format!(
+                "    a1423422d5e4819d6389557b294103529a6fc66a x86_64   {}",
+                elf.to_string_lossy()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_uuid_ndjson() {
+    let obj_path = fixture("inlined_macho");
+
+    assert_eq!(
+        ators_lines(&[
+            "--uuid",
+            "--format",
+            "ndjson",
+            "-o",
+            &obj_path.to_string_lossy()
+        ]),
+        [serde_json::json!({
+            "uuid": "4C4C44CA-5555-3144-A147-1FD6514C2882",
+            "arch": "x86_64",
+            "path": obj_path.to_string_lossy(),
+        })
+        .to_string()]
+    );
+}
+
+#[test]
+fn test_uuid_errors() {
+    let macho = fixture("inlined_macho");
+    let obj = fixture("elf.o");
+    let elf = fixture("inlined_elf");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_ators"))
+        .args([
+            "--uuid",
+            "-o",
+            &macho.to_string_lossy(),
+            "-o",
+            &obj.to_string_lossy(),
+            "-o",
+            &elf.to_string_lossy(),
+        ])
+        .output()
+        .expect("ators to run");
+
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).lines().count(),
+        2,
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .any(|line| line.starts_with(&*obj.to_string_lossy())));
+}
@@ -0,0 +1,72 @@
+use crate::data::*;
+use std::io::BufRead;
+
+/// How the addresses to symbolicate are found in the lines of an input.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum AddrInput {
+    /// Every whitespace-separated token of a line is an address, in hex with a `0x` prefix,
+    /// or in decimal, or in hex without a prefix when it isn't decimal. Anything after a `#`
+    /// is a comment.
+    #[default]
+    Tokens,
+
+    /// The address of a line is the first `0x`-prefixed hex number in it, as the address of a
+    /// frame in a backtrace, and any other text is ignored, as are lines without one.
+    Extract,
+}
+
+impl AddrInput {
+    /// Reads the addresses of every line of `reader`, along with an error for each token that
+    /// isn't one.
+    pub fn read<R: BufRead>(self, reader: R) -> impl Iterator<Item = Result<Addr, Error>> {
+        reader
+            .lines()
+            .enumerate()
+            .flat_map(move |(index, line)| match line {
+                Ok(line) => self.parse_line(index + 1, &line),
+                Err(err) => vec![Err(Error::from(err))],
+            })
+    }
+
+    /// The addresses of `line`, the `number`th line of an input, along with an error for each
+    /// token that isn't one.
+    pub fn parse_line(self, number: usize, line: &str) -> Vec<Result<Addr, Error>> {
+        match self {
+            AddrInput::Tokens => line
+                .split('#')
+                .next()
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(|token| {
+                    token
+                        .parse()
+                        .map_err(|_| Error::AddrInputInvalid(number, token.to_string()))
+                })
+                .collect(),
+
+            AddrInput::Extract => extract(line).into_iter().map(Ok).collect(),
+        }
+    }
+}
+
+/// The first `0x`-prefixed hex number of `line` that starts a word.
+fn extract(line: &str) -> Option<Addr> {
+    let bytes = line.as_bytes();
+
+    (0..bytes.len()).find_map(|index| {
+        let starts_word = index == 0 || !bytes[index - 1].is_ascii_alphanumeric();
+        if !starts_word || !matches!(bytes[index..], [b'0', b'x' | b'X', ..]) {
+            return None;
+        }
+
+        let digits = &bytes[index + 2..];
+        let len = digits
+            .iter()
+            .position(|byte| !byte.is_ascii_hexdigit())
+            .unwrap_or(digits.len());
+
+        u64::from_str_radix(std::str::from_utf8(&digits[..len]).ok()?, 16)
+            .ok()
+            .map(Addr::from)
+    })
+}
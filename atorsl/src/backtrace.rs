@@ -0,0 +1,201 @@
+use crate::{
+    crash_report::{parse_text_frame, text_symbol},
+    data::*,
+};
+use std::ops::Range;
+
+/// A frame in a line of backtrace text, in any of the formats:
+///
+/// - Apple crash reports, and `backtrace_symbols` on Apple platforms:
+///   `3   MyApp   0x0000000100369e4c 0x100360000 + 40524`, or `... main + 28`.
+/// - `backtrace_symbols` on glibc: `./myapp(main+0x1c) [0x55d5c2a3b4c5]`.
+/// - Rust panic backtraces: `  10:     0x55d5c2a3b4c5 - myapp::main::h1234`.
+/// - Android tombstones: `    #03 pc 0000000000042f1c  /system/lib64/libfoo.so (foo+164)`.
+///
+/// Symbols are written in place of the symbol of the frame, or where the format writes it
+/// when it has none, leaving the rest of the line untouched.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BacktraceFrame {
+    /// The name or path of the binary image of the frame, when the format has one.
+    pub image: Option<String>,
+
+    /// Whether `image` may be the start of the name of the binary image, as Apple crash
+    /// reports truncate it to the width of its column.
+    pub image_truncated: bool,
+
+    /// The address of the frame in the running process.
+    pub addr: Addr,
+
+    /// The location of the binary image `addr` is relative to, when the line has it, as the
+    /// load address of Apple crash reports, or the addresses of the binary image as built of
+    /// Android tombstones.
+    pub base_addr: Option<Loc>,
+
+    /// The span of the line written over by the symbols of the frame.
+    symbol_span: Range<usize>,
+
+    /// The text written before and after each symbol within the span.
+    symbol_affixes: (&'static str, &'static str),
+}
+
+impl BacktraceFrame {
+    /// Parses the frame in `line`, if it's the frame line of a backtrace.
+    pub fn parse(line: &str) -> Option<Self> {
+        parse_apple(line)
+            .or_else(|| parse_rust(line))
+            .or_else(|| parse_android(line))
+            .or_else(|| parse_glibc(line))
+    }
+
+    /// Rewrites `line`, the line the frame was parsed from, with `symbols` in place of the
+    /// symbol of the frame, a line for each, innermost first, joined by newlines.
+    pub fn rewrite(&self, line: &str, symbols: &[Symbol]) -> String {
+        let (before, after) = self.symbol_affixes;
+
+        symbols
+            .iter()
+            .map(|symbol| {
+                format!(
+                    "{}{before}{}{after}{}",
+                    &line[..self.symbol_span.start],
+                    text_symbol(symbol),
+                    &line[self.symbol_span.end..]
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Parses the frame of a `.crash`, where the address is followed either by the load address
+/// of the image and the offset into it, or by a symbol and the offset into it.
+fn parse_apple(line: &str) -> Option<BacktraceFrame> {
+    let (prefix_len, image, addr) = parse_text_frame(line)?;
+
+    let load_addr = line[prefix_len..]
+        .trim()
+        .split_once(" + ")
+        .filter(|(load_addr, _)| load_addr.starts_with("0x"))
+        .and_then(|(load_addr, _)| load_addr.parse().ok());
+
+    Some(BacktraceFrame {
+        image: Some(image.to_owned()),
+        image_truncated: true,
+        addr,
+        base_addr: load_addr.map(Loc::Load),
+        symbol_span: prefix_len..line.len(),
+        symbol_affixes: (" ", ""),
+    })
+}
+
+/// Parses the frame of a Rust backtrace, along with its symbol after a ` - ` when it has one.
+fn parse_rust(line: &str) -> Option<BacktraceFrame> {
+    let (index, rest) = line.trim_start().split_once(':')?;
+    index.parse::<usize>().ok()?;
+
+    let rest = rest.trim_start().strip_prefix("0x")?;
+    let addr_len = rest
+        .find(|char: char| !char.is_ascii_hexdigit())
+        .unwrap_or(rest.len());
+    let addr = u64::from_str_radix(&rest[..addr_len], 16).ok()?;
+    let addr_end = line.len() - rest.len() + addr_len;
+
+    let (symbol_span, symbol_affixes) = match line[addr_end..].find(" - ") {
+        Some(dash) => (addr_end + dash + 3..line.len(), ("", "")),
+        None => (addr_end..addr_end, (" - ", "")),
+    };
+
+    Some(BacktraceFrame {
+        image: None,
+        image_truncated: false,
+        addr: Addr::from(addr),
+        base_addr: None,
+        symbol_span,
+        symbol_affixes,
+    })
+}
+
+/// Parses the frame of an Android tombstone, whose address is relative to its image, along
+/// with the symbol in parentheses after the image when it has one, and after the offset of
+/// the image in its APK when it's mapped from one, as `(offset 0x1d000)`.
+fn parse_android(line: &str) -> Option<BacktraceFrame> {
+    let rest = line.trim_start().strip_prefix('#')?;
+    let (index, rest) = rest.split_once(char::is_whitespace)?;
+    index.parse::<usize>().ok()?;
+
+    let rest = rest.trim_start().strip_prefix("pc")?.trim_start();
+    let (addr, rest) = rest.split_once(char::is_whitespace)?;
+    let addr = Addr::from(u64::from_str_radix(addr, 16).ok()?);
+
+    let rest = rest.trim_start();
+    let image_len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+    let image_end = line.len() - rest.len() + image_len;
+
+    let mut symbol_end = image_end;
+    let mut symbol = line[image_end..].trim_start();
+    if symbol.starts_with("(offset 0x") {
+        symbol_end = line.len() - symbol.len() + closing_paren(symbol)? + 1;
+        symbol = line[symbol_end..].trim_start();
+    }
+
+    let (symbol_span, symbol_affixes) = match closing_paren(symbol) {
+        Some(close) if !symbol.starts_with("(BuildId:") => {
+            let start = line.len() - symbol.len();
+            (start..start + close + 1, ("(", ")"))
+        }
+        _ => (symbol_end..symbol_end, (" (", ")")),
+    };
+
+    Some(BacktraceFrame {
+        image: Some(rest[..image_len].to_owned()),
+        image_truncated: false,
+        addr,
+        base_addr: Some(Loc::Slide(Addr::nil())),
+        symbol_span,
+        symbol_affixes,
+    })
+}
+
+/// Parses the frame of `backtrace_symbols` on glibc, the image followed by the symbol in
+/// parentheses, empty when it has none, and by the address in brackets.
+fn parse_glibc(line: &str) -> Option<BacktraceFrame> {
+    let trimmed = line.trim_end();
+    let (rest, addr) = trimmed.strip_suffix(']')?.rsplit_once(" [0x")?;
+    let addr = Addr::from(u64::from_str_radix(addr, 16).ok()?);
+
+    let (image, symbol) = rest.strip_suffix(')')?.split_once('(')?;
+    let start = image.len() + 1;
+
+    Some(BacktraceFrame {
+        image: Some(image.trim_start().to_owned()).filter(|image| !image.is_empty()),
+        image_truncated: false,
+        addr,
+        base_addr: None,
+        symbol_span: start..start + symbol.len(),
+        symbol_affixes: ("", ""),
+    })
+}
+
+/// The index of the parenthesis closing the one `text` starts with, past the ones nested in
+/// it, as in the arguments of a C++ symbol.
+fn closing_paren(text: &str) -> Option<usize> {
+    if !text.starts_with('(') {
+        return None;
+    }
+
+    let mut depth = 0usize;
+    for (index, char) in text.char_indices() {
+        match char {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
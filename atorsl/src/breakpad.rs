@@ -0,0 +1,671 @@
+use crate::{
+    data::*,
+    demangler,
+    ext::object::{Architecture as _, File as _},
+    symbolicator::{Function, Symbolicator},
+};
+use itertools::Either;
+use object::{Object, ObjectSegment};
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    fs,
+    io::Read,
+    mem,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+/// A Breakpad symbol file, the text format Breakpad's `dump_syms` writes the debug info of a
+/// binary image in.
+///
+/// Addresses in a symbol file are relative to the base address of the binary image: the
+/// `__TEXT` segment of a Mach-O, the first loadable segment of an ELF, or the image base of
+/// a PE. `FUNC` records, along with their line and `INLINE` records, give the functions with
+/// debug info, and `PUBLIC` records the symbols of the symbol table without any.
+///
+/// [`dump`] also records the base address in an `INFO BASE_ADDRESS` record, for the addresses
+/// of the binary image to be symbolicated as built. That record is an extension of ators, not
+/// part of the Breakpad format: other tools skip it, and the files of Breakpad's `dump_syms`
+/// don't have it. Their base address is taken as 0, so their addresses are symbolicated as
+/// offsets into the binary image, as those given with a load address or `--offset` are.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BreakpadSym {
+    pub module: BreakpadModule,
+
+    /// The base address of the binary image, or 0 without the `INFO BASE_ADDRESS` record that
+    /// only ators writes.
+    pub base_addr: Addr,
+
+    files: HashMap<u64, PathBuf>,
+    inline_origins: HashMap<u64, String>,
+    funcs: Vec<BreakpadFunc>,
+    publics: Vec<BreakpadPublic>,
+}
+
+/// The `MODULE` record of a Breakpad symbol file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BreakpadModule {
+    pub os: String,
+    pub arch: String,
+    pub id: String,
+    pub name: String,
+}
+
+/// A `FUNC` record, along with its line and `INLINE` records.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct BreakpadFunc {
+    range: Range<u64>,
+    name: String,
+    lines: Vec<BreakpadLine>,
+    inlines: Vec<BreakpadInline>,
+}
+
+/// A line record of a `FUNC` record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct BreakpadLine {
+    range: Range<u64>,
+    line: u64,
+    file: u64,
+}
+
+/// An `INLINE` record, the ranges of a function inlined `depth` calls deep into a `FUNC`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct BreakpadInline {
+    depth: usize,
+    call_line: u64,
+    call_file: u64,
+    origin: u64,
+    ranges: Vec<Range<u64>>,
+}
+
+/// A `PUBLIC` record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct BreakpadPublic {
+    addr: u64,
+    name: String,
+}
+
+impl BreakpadSym {
+    /// Whether the file at `path` starts with the `MODULE` record of a Breakpad symbol file.
+    pub fn is_sym(path: &Path) -> bool {
+        let mut magic = [0; 7];
+        fs::File::open(path)
+            .and_then(|mut file| file.read_exact(&mut magic))
+            .is_ok_and(|_| &magic == b"MODULE ")
+    }
+
+    /// Parses the Breakpad symbol file in `text`.
+    ///
+    /// `STACK`, unknown records and `INFO` records other than `INFO BASE_ADDRESS` are skipped,
+    /// and line and `INLINE` records are only taken after a `FUNC` record.
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let mut sym = Self::default();
+
+        for (index, line) in text.lines().enumerate() {
+            let invalid = || Error::BreakpadSymInvalid(index + 1);
+            let (record, rest) = line.split_once(' ').unwrap_or((line, ""));
+
+            match record {
+                "MODULE" => {
+                    let mut fields = rest.splitn(4, ' ');
+                    let mut field = || fields.next().map(String::from).ok_or_else(invalid);
+
+                    sym.module = BreakpadModule {
+                        os: field()?,
+                        arch: field()?,
+                        id: field()?,
+                        name: field()?,
+                    };
+                }
+
+                "FILE" => {
+                    let (number, name) = rest.split_once(' ').ok_or_else(invalid)?;
+                    sym.files
+                        .insert(number.parse().map_err(|_| invalid())?, PathBuf::from(name));
+                }
+
+                "INLINE_ORIGIN" => {
+                    let (number, name) = rest.split_once(' ').ok_or_else(invalid)?;
+                    sym.inline_origins
+                        .insert(number.parse().map_err(|_| invalid())?, name.to_owned());
+                }
+
+                "FUNC" => {
+                    let rest = rest.strip_prefix("m ").unwrap_or(rest);
+                    let mut fields = rest.splitn(4, ' ');
+                    let addr = hex(fields.next()).ok_or_else(invalid)?;
+                    let size = hex(fields.next()).ok_or_else(invalid)?;
+                    let name = fields.nth(1).ok_or_else(invalid)?;
+
+                    sym.funcs.push(BreakpadFunc {
+                        range: addr..addr + size,
+                        name: name.to_owned(),
+                        lines: Vec::default(),
+                        inlines: Vec::default(),
+                    });
+                }
+
+                "INLINE" => {
+                    let fields = rest.split(' ').collect::<Vec<_>>();
+                    let [depth, call_line, call_file, origin, ranges @ ..] = &fields[..] else {
+                        return Err(invalid());
+                    };
+
+                    let inline = BreakpadInline {
+                        depth: depth.parse().map_err(|_| invalid())?,
+                        call_line: call_line.parse().map_err(|_| invalid())?,
+                        call_file: call_file.parse().map_err(|_| invalid())?,
+                        origin: origin.parse().map_err(|_| invalid())?,
+                        ranges: ranges
+                            .chunks(2)
+                            .map(|range| {
+                                let addr = hex(range.first().copied())?;
+                                Some(addr..addr + hex(range.get(1).copied())?)
+                            })
+                            .collect::<Option<_>>()
+                            .ok_or_else(invalid)?,
+                    };
+
+                    if let Some(func) = sym.funcs.last_mut() {
+                        func.inlines.push(inline);
+                    }
+                }
+
+                "PUBLIC" => {
+                    let rest = rest.strip_prefix("m ").unwrap_or(rest);
+                    let mut fields = rest.splitn(3, ' ');
+                    let addr = hex(fields.next()).ok_or_else(invalid)?;
+                    let name = fields.nth(1).ok_or_else(invalid)?;
+
+                    sym.publics
+                        .push(BreakpadPublic { addr, name: name.to_owned() });
+                }
+
+                "INFO" => {
+                    if let Some(base_addr) = rest.strip_prefix("BASE_ADDRESS ") {
+                        sym.base_addr = Addr::from(hex(Some(base_addr)).ok_or_else(invalid)?);
+                    }
+                }
+
+                "STACK" => {}
+
+                _ if record.starts_with(|c: char| c.is_ascii_hexdigit()) => {
+                    let mut fields = line.splitn(4, ' ');
+                    let addr = hex(fields.next()).ok_or_else(invalid)?;
+                    let size = hex(fields.next()).ok_or_else(invalid)?;
+                    let (Some(line), Some(file)) = (fields.next(), fields.next()) else {
+                        return Err(invalid());
+                    };
+
+                    if let Some(func) = sym.funcs.last_mut() {
+                        func.lines.push(BreakpadLine {
+                            range: addr..addr + size,
+                            line: line.parse().map_err(|_| invalid())?,
+                            file: file.parse().map_err(|_| invalid())?,
+                        });
+                    }
+                }
+
+                _ => {}
+            }
+        }
+
+        sym.funcs.sort_by_key(|func| func.range.start);
+        sym.publics.sort_by_key(|public| public.addr);
+
+        Ok(sym)
+    }
+
+    /// The range of addresses of the binary image covered by the records of the symbol file, up
+    /// to the last public symbol, whose size isn't recorded.
+    pub fn addr_range(&self) -> Range<u64> {
+        let start = self
+            .funcs
+            .first()
+            .map(|func| func.range.start)
+            .into_iter()
+            .chain(self.publics.first().map(|public| public.addr))
+            .min()
+            .unwrap_or_default();
+
+        let end = self
+            .funcs
+            .iter()
+            .map(|func| func.range.end)
+            .chain(self.publics.last().map(|public| public.addr + 1))
+            .max()
+            .unwrap_or_default();
+
+        *self.base_addr + start..*self.base_addr + end
+    }
+
+    /// An estimate of the memory taken by the records of the symbol file once parsed, in bytes.
+    pub fn memory_size(&self) -> usize {
+        let files = self
+            .files
+            .values()
+            .map(|file| mem::size_of::<(u64, PathBuf)>() + file.as_os_str().len());
+
+        let inline_origins = self
+            .inline_origins
+            .values()
+            .map(|origin| mem::size_of::<(u64, String)>() + origin.len());
+
+        let funcs = self.funcs.iter().map(|func| {
+            mem::size_of::<BreakpadFunc>()
+                + func.name.len()
+                + mem::size_of_val(&func.lines[..])
+                + func
+                    .inlines
+                    .iter()
+                    .map(|inline| {
+                        mem::size_of::<BreakpadInline>() + mem::size_of_val(&inline.ranges[..])
+                    })
+                    .sum::<usize>()
+        });
+
+        let publics = self
+            .publics
+            .iter()
+            .map(|public| mem::size_of::<BreakpadPublic>() + public.name.len());
+
+        files
+            .chain(inline_origins)
+            .chain(funcs)
+            .chain(publics)
+            .sum()
+    }
+
+    /// Symbolicates `addr`, an address of the binary image as built at the base address of the
+    /// symbol file, returning the innermost frame first when `include_inlined` is set.
+    ///
+    /// Addresses outside of every `FUNC` record are symbolicated to the `PUBLIC` record
+    /// preceding them, with an offset into it.
+    pub fn atos(&self, addr: Addr, include_inlined: bool) -> Result<Frames, Error> {
+        let rva = addr
+            .checked_sub(*self.base_addr)
+            .ok_or(Error::AddrNotFound(addr))?;
+
+        let Some(func) = self
+            .funcs
+            .partition_point(|func| func.range.start <= rva)
+            .checked_sub(1)
+            .map(|index| &self.funcs[index])
+            .filter(|func| func.range.contains(&rva))
+        else {
+            return self.atos_public(addr, rva);
+        };
+
+        let line = func
+            .lines
+            .iter()
+            .find(|line| line.range.contains(&rva));
+
+        let loc = match line {
+            Some(line) => Either::Left(self.source_loc(line.file, line.line)),
+            None => Either::Right(Offset::from(rva - func.range.start)),
+        };
+
+        if !include_inlined {
+            return Ok(Frames {
+                source: Source::Breakpad,
+                symbols: vec![symbol(addr, &func.name, loc, false)],
+            });
+        }
+
+        let mut inlines = func
+            .inlines
+            .iter()
+            .filter(|inline| inline.ranges.iter().any(|range| range.contains(&rva)))
+            .collect::<Vec<_>>();
+
+        inlines.sort_by_key(|inline| inline.depth);
+
+        // Each frame is named after the function called at the location of the next one.
+        let mut symbols = Vec::default();
+        let mut caller = func.name.as_str();
+
+        for inline in inlines {
+            symbols.push(symbol(
+                addr,
+                caller,
+                Either::Left(self.source_loc(inline.call_file, inline.call_line)),
+                !symbols.is_empty(),
+            ));
+
+            caller = self
+                .inline_origins
+                .get(&inline.origin)
+                .map(String::as_str)
+                .unwrap_or_default();
+        }
+
+        symbols.push(symbol(addr, caller, loc, !symbols.is_empty()));
+        symbols.reverse();
+
+        Ok(Frames { source: Source::Breakpad, symbols })
+    }
+
+    fn atos_public(&self, addr: Addr, rva: u64) -> Result<Frames, Error> {
+        let public = self
+            .publics
+            .partition_point(|public| public.addr <= rva)
+            .checked_sub(1)
+            .map(|index| &self.publics[index])
+            .ok_or(Error::AddrNotFound(addr))?;
+
+        Ok(Frames {
+            source: Source::SymbolTable,
+            symbols: vec![symbol(
+                addr,
+                &public.name,
+                Either::Right(Offset::from(rva - public.addr)),
+                false,
+            )],
+        })
+    }
+
+    fn source_loc(&self, file: u64, line: u64) -> SourceLoc {
+        SourceLoc {
+            file: self.files.get(&file).cloned().unwrap_or_default(),
+            line,
+            col: 0,
+        }
+    }
+}
+
+/// A Breakpad symbol file written by [`dump`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BreakpadDump {
+    /// The text of the symbol file.
+    pub sym: String,
+
+    /// The names of the functions left out for starting below the base address, which the
+    /// format can't record, as those of sections discarded at link time, left at address 0.
+    pub skipped: Vec<String>,
+}
+
+/// Writes the debug info of the binary image `obj` named `name`, as indexed by
+/// `symbolicator`, as a Breakpad symbol file.
+///
+/// A `FUNC` record is written for each range of every function with debug info, with a line
+/// record for each line row and an `INLINE` record for each function inlined into it, and a
+/// `PUBLIC` record for each symbol in the symbol table outside of them.
+pub fn dump(
+    obj: &object::File,
+    symbolicator: &Symbolicator,
+    name: &str,
+) -> Result<BreakpadDump, Error> {
+    let base = base_addr(obj);
+
+    let (mut functions, skipped) = symbolicator
+        .functions()?
+        .into_iter()
+        .partition::<Vec<_>, _>(|function| {
+            function.ranges.iter().all(|range| range.start >= base)
+        });
+    functions.sort_by_key(|function| function.ranges.first().map(|range| range.start));
+
+    let mut files = Numbering::<PathBuf>::default();
+    let mut origins = Numbering::<String>::default();
+    // Source paths are written absolute, as the call locations of inlined functions are.
+    for function in &mut functions {
+        for (_, loc) in &mut function.lines {
+            loc.file = function.comp_dir.join(&loc.file);
+        }
+    }
+
+    for function in &functions {
+        for (_, loc) in &function.lines {
+            files.number(&loc.file);
+        }
+
+        for inlined in &function.inlined {
+            files.number(&inlined.call_loc.file);
+            origins.number(&inlined.name);
+        }
+    }
+
+    let mut sym = String::default();
+
+    writeln!(
+        sym,
+        "MODULE {} {} {} {}",
+        os(obj),
+        arch(obj),
+        module_id(obj)?,
+        name
+    )?;
+    writeln!(sym, "INFO BASE_ADDRESS {base:x}")?;
+
+    for (number, file) in files.values.iter().enumerate() {
+        writeln!(sym, "FILE {} {}", number, file.to_string_lossy())?;
+    }
+
+    for (number, origin) in origins.values.iter().enumerate() {
+        writeln!(sym, "INLINE_ORIGIN {number} {origin}")?;
+    }
+
+    let mut func_ranges = Vec::default();
+    let mut records = Vec::default();
+
+    for function in &functions {
+        for range in &function.ranges {
+            func_ranges.push(range.clone());
+            records.push((
+                range.start,
+                write_func(function, range, base, &files, &origins)?,
+            ));
+        }
+    }
+
+    func_ranges.sort_by_key(|range| range.start);
+
+    let symbol_map = obj.symbol_map();
+    for symbol in symbol_map.symbols() {
+        let addr = symbol.address();
+        if addr < base
+            || func_ranges
+                .get(
+                    func_ranges
+                        .partition_point(|range| range.start <= addr)
+                        .wrapping_sub(1),
+                )
+                .is_some_and(|range| range.contains(&addr))
+        {
+            continue;
+        }
+
+        let name = symbol.name();
+        let name = match obj.format() {
+            object::BinaryFormat::MachO => name.strip_prefix('_').unwrap_or(name),
+            _ => name,
+        };
+
+        records.push((
+            addr,
+            format!("PUBLIC {:x} 0 {}\n", addr - base, demangler::demangle(name)),
+        ));
+    }
+
+    records.sort_by_key(|(addr, _)| *addr);
+    for (_, record) in records {
+        sym.push_str(&record);
+    }
+
+    Ok(BreakpadDump {
+        sym,
+        skipped: skipped
+            .into_iter()
+            .map(|function| function.name)
+            .collect(),
+    })
+}
+
+/// The `FUNC` record of `function` in `range`, followed by its `INLINE` and line records.
+fn write_func(
+    function: &Function,
+    range: &Range<u64>,
+    base: u64,
+    files: &Numbering<PathBuf>,
+    origins: &Numbering<String>,
+) -> Result<String, Error> {
+    let mut record = String::default();
+
+    writeln!(
+        record,
+        "FUNC {:x} {:x} 0 {}",
+        range.start - base,
+        range.end - range.start,
+        function.name
+    )?;
+
+    for inlined in &function.inlined {
+        let ranges = inlined
+            .ranges
+            .iter()
+            .filter(|inlined_range| {
+                range.start <= inlined_range.start && inlined_range.end <= range.end
+            })
+            .map(|inlined_range| {
+                format!(
+                    " {:x} {:x}",
+                    inlined_range.start - base,
+                    inlined_range.end - inlined_range.start
+                )
+            })
+            .collect::<String>();
+
+        if !ranges.is_empty() {
+            writeln!(
+                record,
+                "INLINE {} {} {} {}{}",
+                inlined.depth,
+                inlined.call_loc.line,
+                files.get(&inlined.call_loc.file),
+                origins.get(&inlined.name),
+                ranges
+            )?;
+        }
+    }
+
+    for (line_range, loc) in &function.lines {
+        if range.start <= line_range.start && line_range.end <= range.end {
+            writeln!(
+                record,
+                "{:x} {:x} {} {}",
+                line_range.start - base,
+                line_range.end - line_range.start,
+                loc.line,
+                files.get(&loc.file)
+            )?;
+        }
+    }
+
+    Ok(record)
+}
+
+/// Numbers values in the order they're first found, as `FILE` and `INLINE_ORIGIN` records are.
+#[derive(Default)]
+struct Numbering<T> {
+    values: Vec<T>,
+    numbers: HashMap<T, usize>,
+}
+
+impl<T: Clone + Eq + std::hash::Hash> Numbering<T> {
+    fn number(&mut self, value: &T) {
+        if !self.numbers.contains_key(value) {
+            self.numbers.insert(value.clone(), self.values.len());
+            self.values.push(value.clone());
+        }
+    }
+
+    fn get(&self, value: &T) -> usize {
+        self.numbers.get(value).copied().unwrap_or_default()
+    }
+}
+
+/// The address addresses in the symbol file of `obj` are relative to.
+fn base_addr(obj: &object::File) -> u64 {
+    match obj.vmaddr() {
+        Ok(vmaddr) => *vmaddr,
+        Err(_) => obj
+            .segments()
+            .map(|segment| segment.address())
+            .min()
+            .unwrap_or_default(),
+    }
+}
+
+fn os(obj: &object::File) -> &'static str {
+    match obj.format() {
+        object::BinaryFormat::MachO => "mac",
+        object::BinaryFormat::Elf => "Linux",
+        object::BinaryFormat::Pe | object::BinaryFormat::Coff => "windows",
+        _ => "unknown",
+    }
+}
+
+fn arch(obj: &object::File) -> String {
+    match obj.architecture() {
+        object::Architecture::I386 => String::from("x86"),
+        object::Architecture::PowerPc => String::from("ppc"),
+        object::Architecture::PowerPc64 => String::from("ppc64"),
+        arch => arch.name(),
+    }
+}
+
+/// The identifier of `obj` in the `MODULE` record, formatted as Breakpad does: the UUID of a
+/// Mach-O, the first 16 bytes of the build ID of an ELF, or the GUID of the PDB of a PE,
+/// followed by the age of the PDB, or 0 for the others.
+fn module_id(obj: &object::File) -> Result<String, Error> {
+    if let Some(uuid) = obj.mach_uuid()? {
+        return Ok(format!("{}0", upper_hex(&uuid)));
+    }
+
+    if let Some(code_view) = obj.pdb_info()? {
+        return Ok(format!(
+            "{}{:x}",
+            upper_hex(&guid(code_view.guid())),
+            code_view.age()
+        ));
+    }
+
+    let mut id = [0; 16];
+    if let Some(build_id) = obj.build_id()? {
+        let len = build_id.len().min(id.len());
+        id[..len].copy_from_slice(&build_id[..len]);
+    }
+
+    Ok(format!("{}0", upper_hex(&guid(id))))
+}
+
+/// The bytes of a GUID stored little-endian, in the order it's printed in.
+fn guid(mut bytes: [u8; 16]) -> [u8; 16] {
+    bytes[..4].reverse();
+    bytes[4..6].reverse();
+    bytes[6..8].reverse();
+    bytes
+}
+
+fn upper_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect()
+}
+
+fn hex(field: Option<&str>) -> Option<u64> {
+    u64::from_str_radix(field?, 16).ok()
+}
+
+fn symbol(addr: Addr, name: &str, loc: Either<SourceLoc, Offset>, inlined: bool) -> Symbol {
+    Symbol {
+        addr,
+        name: name.to_owned(),
+        mangled_name: None,
+        loc,
+        inlined,
+    }
+}
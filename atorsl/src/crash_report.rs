@@ -0,0 +1,409 @@
+use crate::data::*;
+use itertools::Either;
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// An Apple crash report, either in the legacy text `.crash` format, or in the JSON `.ips`
+/// format written since iOS 15 and macOS 12.
+///
+/// A frame is located in a binary image of the crashed process, listed along with its load
+/// address and UUID, under "Binary Images:" in a `.crash`, and in `usedImages` in an `.ips`.
+/// Symbolicating a report rewrites its frames in place, leaving the rest of it untouched.
+#[derive(Clone, Debug)]
+pub struct CrashReport {
+    images: Vec<CrashImage>,
+    format: CrashReportFormat,
+}
+
+/// A binary image loaded in the crashed process.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CrashImage {
+    pub name: String,
+    pub uuid: Option<[u8; 16]>,
+    pub arch: Option<String>,
+    pub path: Option<String>,
+    pub load_addr: Addr,
+}
+
+#[derive(Clone, Debug)]
+enum CrashReportFormat {
+    /// The lines of a `.crash`, and its frames.
+    Text {
+        lines: Vec<String>,
+        frames: Vec<TextFrame>,
+    },
+
+    /// The header line of an `.ips`, followed by its body.
+    Ips { header: String, body: Value },
+}
+
+/// A frame in a line of a `.crash`, as in `3  MyApp  0x0000000104a9c3f4 0x104a98000 + 17396`.
+#[derive(Clone, Copy, Debug)]
+struct TextFrame {
+    line: usize,
+
+    /// The length of the line up to the end of the address, which is kept when rewriting it.
+    prefix_len: usize,
+
+    image: usize,
+    addr: Addr,
+}
+
+/// The keys of the backtraces in the body of an `.ips`.
+const IPS_BACKTRACES: [&str; 2] = ["threads", "lastExceptionBacktrace"];
+
+impl CrashReport {
+    /// Parses a `.crash` or `.ips` crash report.
+    ///
+    /// An `.ips` is told apart by its JSON header line, but is parsed as a `.crash` when its
+    /// body isn't JSON, as some crash reports embed the legacy text format.
+    pub fn parse(report: &str) -> Result<Self, Error> {
+        if let Some((header, body)) = report.split_once('\n') {
+            if let (Ok(Value::Object(_)), Ok(body)) = (
+                serde_json::from_str::<Value>(header),
+                serde_json::from_str::<Value>(body),
+            ) {
+                return Self::parse_ips(header, body);
+            }
+        }
+
+        Ok(Self::parse_text(report))
+    }
+
+    /// The binary images loaded in the crashed process.
+    pub fn images(&self) -> &[CrashImage] {
+        &self.images
+    }
+
+    /// Rewrites the crash report with its frames symbolicated.
+    ///
+    /// `symbolicate` is given the index in [`images`] of the binary image of each frame,
+    /// and the address of the frame, and returns its symbols, innermost first, or `None` to
+    /// leave the frame untouched. Inlined symbols are written as frames of their own, at the
+    /// same address, right before the frame of the symbol they were inlined into.
+    ///
+    /// [`images`]: Self::images
+    pub fn symbolicate<F>(&self, mut symbolicate: F) -> Result<String, Error>
+    where
+        F: FnMut(usize, Addr) -> Option<Vec<Symbol>>,
+    {
+        match &self.format {
+            CrashReportFormat::Text { lines, frames } => {
+                let mut frames = frames.iter().peekable();
+                let mut report = String::with_capacity(lines.iter().map(String::len).sum());
+
+                for (index, line) in lines.iter().enumerate() {
+                    let symbols = frames
+                        .next_if(|frame| frame.line == index)
+                        .and_then(|frame| Some((frame, symbolicate(frame.image, frame.addr)?)))
+                        .filter(|(_, symbols)| !symbols.is_empty());
+
+                    match symbols {
+                        Some((frame, symbols)) => symbols.iter().for_each(|symbol| {
+                            report.push_str(&line[..frame.prefix_len]);
+                            report.push(' ');
+                            report.push_str(&text_symbol(symbol));
+                            report.push('\n');
+                        }),
+                        None => {
+                            report.push_str(line);
+                            report.push('\n');
+                        }
+                    }
+                }
+
+                Ok(report)
+            }
+
+            CrashReportFormat::Ips { header, body } => {
+                let mut body = body.clone();
+
+                for key in IPS_BACKTRACES {
+                    match body.get_mut(key) {
+                        Some(Value::Array(threads)) if key == "threads" => threads
+                            .iter_mut()
+                            .filter_map(|thread| thread.get_mut("frames"))
+                            .for_each(|frames| {
+                                self.symbolicate_ips_frames(frames, &mut symbolicate)
+                            }),
+                        Some(frames) => self.symbolicate_ips_frames(frames, &mut symbolicate),
+                        None => {}
+                    }
+                }
+
+                Ok(format!(
+                    "{header}\n{}\n",
+                    serde_json::to_string_pretty(&body).map_err(|_| Error::CrashReportInvalid)?
+                ))
+            }
+        }
+    }
+
+    fn parse_text(report: &str) -> Self {
+        let lines = report.lines().map(String::from).collect::<Vec<_>>();
+
+        let images = lines
+            .iter()
+            .skip_while(|line| !line.starts_with("Binary Images:"))
+            .skip(1)
+            .map_while(|line| parse_text_image(line))
+            .collect::<Vec<_>>();
+
+        let frames = lines
+            .iter()
+            .enumerate()
+            .filter_map(|(index, line)| {
+                let (prefix_len, image_name, addr) = parse_text_frame(line)?;
+                let image = images
+                    .iter()
+                    .position(|(image, range)| {
+                        range.contains(&*addr) && image.name.starts_with(image_name)
+                    })
+                    .or_else(|| {
+                        images
+                            .iter()
+                            .position(|(_, range)| range.contains(&*addr))
+                    })?;
+
+                Some(TextFrame {
+                    line: index,
+                    prefix_len,
+                    image,
+                    addr,
+                })
+            })
+            .collect();
+
+        Self {
+            images: images.into_iter().map(|(image, _)| image).collect(),
+            format: CrashReportFormat::Text { lines, frames },
+        }
+    }
+
+    fn parse_ips(header: &str, body: Value) -> Result<Self, Error> {
+        let images = body
+            .get("usedImages")
+            .and_then(Value::as_array)
+            .ok_or(Error::CrashReportInvalid)?
+            .iter()
+            .map(|image| {
+                let string = |key| {
+                    image
+                        .get(key)
+                        .and_then(Value::as_str)
+                        .map(String::from)
+                };
+                let path = string("path");
+
+                Ok(CrashImage {
+                    name: string("name")
+                        .or_else(|| {
+                            Some(
+                                Path::new(path.as_ref()?)
+                                    .file_name()?
+                                    .to_str()?
+                                    .to_owned(),
+                            )
+                        })
+                        .unwrap_or_default(),
+                    uuid: string("uuid").as_deref().and_then(parse_uuid),
+                    arch: string("arch"),
+                    path,
+                    load_addr: image
+                        .get("base")
+                        .and_then(Value::as_u64)
+                        .map(Addr::from)
+                        .ok_or(Error::CrashReportInvalid)?,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(Self {
+            images,
+            format: CrashReportFormat::Ips { header: header.to_owned(), body },
+        })
+    }
+
+    fn symbolicate_ips_frames<F>(&self, frames: &mut Value, symbolicate: &mut F)
+    where
+        F: FnMut(usize, Addr) -> Option<Vec<Symbol>>,
+    {
+        let Value::Array(frames) = frames else {
+            return;
+        };
+
+        *frames = frames
+            .drain(..)
+            .flat_map(|frame| {
+                let symbols = self
+                    .ips_frame_addr(&frame)
+                    .and_then(|(image, addr)| symbolicate(image, addr))
+                    .filter(|symbols| !symbols.is_empty());
+
+                match (frame, symbols) {
+                    (Value::Object(frame), Some(symbols)) => Either::Left(
+                        symbols
+                            .into_iter()
+                            .map(move |symbol| Value::Object(ips_symbol(frame.clone(), symbol))),
+                    ),
+                    (frame, _) => Either::Right([frame].into_iter()),
+                }
+            })
+            .collect();
+    }
+
+    /// The image index and address of a frame in an `.ips`, from its offset into the image.
+    fn ips_frame_addr(&self, frame: &Value) -> Option<(usize, Addr)> {
+        let image = usize::try_from(frame.get("imageIndex")?.as_u64()?).ok()?;
+        let offset = frame.get("imageOffset")?.as_u64()?;
+
+        Some((
+            image,
+            Addr::from(self.images.get(image)?.load_addr.checked_add(offset)?),
+        ))
+    }
+}
+
+/// Parses a line of the "Binary Images:" section of a `.crash`, along with the address range
+/// of the image, as in:
+///
+/// `0x104a98000 - 0x104b23fff MyApp arm64  <6c9b2e151f0a3b8a9b9c0d0f1b6c8e4a> /path/to/MyApp`
+///
+/// Older reports prefix the name of images in the app bundle with `+` and follow it with its
+/// version in parentheses.
+fn parse_text_image(line: &str) -> Option<(CrashImage, std::ops::RangeInclusive<u64>)> {
+    let (start, rest) = line.trim().split_once('-')?;
+    let start = start.trim().parse::<Addr>().ok()?;
+
+    let rest = rest.trim_start();
+    let (end, rest) = rest
+        .split_once(char::is_whitespace)
+        .unwrap_or((rest, ""));
+    let end = end.parse::<Addr>().map(|end| *end).unwrap_or(*start);
+
+    let (desc, uuid, path) = match rest
+        .split_once('<')
+        .and_then(|(desc, rest)| Some((desc, rest.split_once('>')?)))
+    {
+        Some((desc, (uuid, path))) => (desc, Some(uuid), path.trim()),
+        None => (rest, None, ""),
+    };
+
+    let desc = desc.trim().trim_start_matches('+');
+    let desc = match desc
+        .strip_suffix(')')
+        .and_then(|desc| desc.rsplit_once('('))
+    {
+        Some((desc, _)) => desc.trim_end(),
+        None => desc,
+    };
+
+    let (name, arch) = match desc.rsplit_once(char::is_whitespace) {
+        Some((name, arch)) if is_arch(arch) => (name.trim_end(), Some(arch.to_owned())),
+        _ => (desc, None),
+    };
+
+    Some((
+        CrashImage {
+            name: name.to_owned(),
+            uuid: uuid.and_then(parse_uuid),
+            arch,
+            path: (!path.is_empty()).then(|| path.to_owned()),
+            load_addr: start,
+        },
+        *start..=end,
+    ))
+}
+
+/// Parses a frame line of a thread backtrace of a `.crash`, returning the length of the line
+/// up to the end of the address, the name of its image and its address, as in:
+///
+/// `3   MyApp                         0x0000000104a9c3f4 0x104a98000 + 17396`
+pub(crate) fn parse_text_frame(line: &str) -> Option<(usize, &str, Addr)> {
+    let (index, rest) = line.trim_start().split_once(char::is_whitespace)?;
+    index.parse::<usize>().ok()?;
+
+    let rest = rest.trim_start();
+    let (addr_start, _) = rest
+        .match_indices("0x")
+        .find(|(index, _)| rest[..*index].ends_with(char::is_whitespace))?;
+
+    let (image, rest) = (&rest[..addr_start], &rest[addr_start + 2..]);
+    let addr_len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+    let addr = u64::from_str_radix(&rest[..addr_len], 16).ok()?;
+
+    Some((
+        line.len() - rest.len() + addr_len,
+        image.trim_end(),
+        Addr::from(addr),
+    ))
+}
+
+/// Parses a UUID, with or without hyphens, as printed in crash reports.
+fn parse_uuid(uuid: &str) -> Option<[u8; 16]> {
+    let hex = uuid.trim().replace('-', "");
+    if hex.len() != 32 {
+        return None;
+    }
+
+    let mut bytes = [0; 16];
+    for (index, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(hex.get(index * 2..index * 2 + 2)?, 16).ok()?;
+    }
+
+    Some(bytes)
+}
+
+fn is_arch(name: &str) -> bool {
+    crate::data::Arch::from_name(name).architecture() != object::Architecture::Unknown
+}
+
+/// A symbol as written in a frame of a `.crash` by Xcode.
+pub(crate) fn text_symbol(symbol: &Symbol) -> String {
+    match &symbol.loc {
+        Either::Left(source_loc) => format!(
+            "{} ({}:{})",
+            symbol.name,
+            source_loc
+                .file
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy(),
+            source_loc.line
+        ),
+        Either::Right(offset) => format!("{} + {}", symbol.name, **offset),
+    }
+}
+
+/// The `frame` of an `.ips` with its symbol replaced by `symbol`.
+fn ips_symbol(mut frame: Map<String, Value>, symbol: Symbol) -> Map<String, Value> {
+    frame.insert(String::from("symbol"), Value::from(symbol.name));
+
+    match symbol.loc {
+        Either::Left(source_loc) => {
+            frame.remove("symbolLocation");
+            frame.insert(
+                String::from("sourceFile"),
+                Value::from(
+                    source_loc
+                        .file
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy(),
+                ),
+            );
+            frame.insert(String::from("sourceLine"), Value::from(source_loc.line));
+        }
+        Either::Right(offset) => {
+            frame.remove("sourceFile");
+            frame.remove("sourceLine");
+            frame.insert(String::from("symbolLocation"), Value::from(*offset));
+        }
+    }
+
+    if symbol.inlined {
+        frame.insert(String::from("inline"), Value::from(true));
+    }
+
+    frame
+}
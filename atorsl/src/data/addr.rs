@@ -62,9 +62,12 @@ impl FromStr for Addr {
     type Err = <u64 as FromStr>::Err;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        s.parse::<u64>()
-            .or_else(|_| u64::from_str_radix(s.trim().trim_start_matches("0x"), 16))
-            .map(Addr::from)
+        let s = s.trim();
+        match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => s.parse().or_else(|_| u64::from_str_radix(s, 16)),
+        }
+        .map(Addr::from)
     }
 }
 
@@ -0,0 +1,114 @@
+use crate::ext::object::Architecture as _;
+use object::macho::*;
+use std::fmt;
+
+/// The name, CPU type and CPU subtype of the Mach-O architectures.
+const MACHO_ARCHS: &[(&str, u32, u32)] = &[
+    ("i386", CPU_TYPE_X86, CPU_SUBTYPE_I386_ALL),
+    ("x86_64", CPU_TYPE_X86_64, CPU_SUBTYPE_X86_64_ALL),
+    ("x86_64h", CPU_TYPE_X86_64, CPU_SUBTYPE_X86_64_H),
+    ("arm", CPU_TYPE_ARM, CPU_SUBTYPE_ARM_ALL),
+    ("armv4t", CPU_TYPE_ARM, CPU_SUBTYPE_ARM_V4T),
+    ("armv5tej", CPU_TYPE_ARM, CPU_SUBTYPE_ARM_V5TEJ),
+    ("armv6", CPU_TYPE_ARM, CPU_SUBTYPE_ARM_V6),
+    ("armv6m", CPU_TYPE_ARM, CPU_SUBTYPE_ARM_V6M),
+    ("armv7", CPU_TYPE_ARM, CPU_SUBTYPE_ARM_V7),
+    ("armv7f", CPU_TYPE_ARM, CPU_SUBTYPE_ARM_V7F),
+    ("armv7s", CPU_TYPE_ARM, CPU_SUBTYPE_ARM_V7S),
+    ("armv7k", CPU_TYPE_ARM, CPU_SUBTYPE_ARM_V7K),
+    ("armv7m", CPU_TYPE_ARM, CPU_SUBTYPE_ARM_V7M),
+    ("armv7em", CPU_TYPE_ARM, CPU_SUBTYPE_ARM_V7EM),
+    ("armv8", CPU_TYPE_ARM, CPU_SUBTYPE_ARM_V8),
+    ("armv8m", CPU_TYPE_ARM, CPU_SUBTYPE_ARM_V8M),
+    ("arm64", CPU_TYPE_ARM64, CPU_SUBTYPE_ARM64_ALL),
+    ("arm64v8", CPU_TYPE_ARM64, CPU_SUBTYPE_ARM64_V8),
+    ("arm64e", CPU_TYPE_ARM64, CPU_SUBTYPE_ARM64E),
+    ("arm64_32", CPU_TYPE_ARM64_32, CPU_SUBTYPE_ARM64_32_ALL),
+    ("arm64_32v8", CPU_TYPE_ARM64_32, CPU_SUBTYPE_ARM64_32_V8),
+    ("ppc", CPU_TYPE_POWERPC, CPU_SUBTYPE_POWERPC_ALL),
+    ("ppc64", CPU_TYPE_POWERPC64, CPU_SUBTYPE_POWERPC_ALL),
+];
+
+/// The architecture of a binary image, told apart down to the CPU subtype of Mach-O slices, as
+/// `arm64e` is from `arm64`, or `armv7s` from `armv7`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Arch {
+    architecture: object::Architecture,
+
+    /// The Mach-O CPU type and subtype, without the capability bits of the subtype.
+    cpu: Option<(u32, u32)>,
+}
+
+impl Arch {
+    /// The architecture named `name`, as given to `-arch`.
+    pub fn from_name(name: &str) -> Self {
+        let macho_name = match name {
+            "x86" => "i386",
+            "aarch64" => "arm64",
+            "powerpc" => "ppc",
+            "powerpc64" => "ppc64",
+            name => name,
+        };
+
+        Self {
+            architecture: object::Architecture::from_name(name),
+            cpu: MACHO_ARCHS
+                .iter()
+                .find(|(arch_name, ..)| *arch_name == macho_name)
+                .map(|(_, cputype, cpusubtype)| (*cputype, *cpusubtype)),
+        }
+    }
+
+    /// The architecture of a Mach-O slice of `cputype` and `cpusubtype`.
+    pub fn from_macho(architecture: object::Architecture, cputype: u32, cpusubtype: u32) -> Self {
+        Self {
+            architecture,
+            cpu: Some((cputype, cpusubtype & !CPU_SUBTYPE_MASK)),
+        }
+    }
+
+    /// The architecture, regardless of its CPU subtype.
+    pub fn architecture(&self) -> object::Architecture {
+        self.architecture
+    }
+
+    /// Whether a slice of architecture `slice` can be selected for `self`, as when they are the
+    /// same, or `self` names every subtype of the CPU type of `slice`, as `arm64` does for an
+    /// `arm64e` slice, although a slice that is the same is preferred.
+    pub fn matches(&self, slice: &Self) -> bool {
+        match (self.cpu, slice.cpu) {
+            (Some((cputype, cpusubtype)), Some((slice_cputype, slice_cpusubtype))) => {
+                cputype == slice_cputype
+                    && (cpusubtype == slice_cpusubtype
+                        || MACHO_ARCHS
+                            .iter()
+                            .find(|(_, arch_cputype, _)| *arch_cputype == cputype)
+                            .is_some_and(|(.., all_cpusubtype)| *all_cpusubtype == cpusubtype))
+            }
+            _ => self.architecture == slice.architecture,
+        }
+    }
+
+    /// The name of the architecture, as given to `-arch`.
+    pub fn name(&self) -> String {
+        self.cpu
+            .and_then(|cpu| {
+                MACHO_ARCHS
+                    .iter()
+                    .find(|(_, cputype, cpusubtype)| (*cputype, *cpusubtype) == cpu)
+            })
+            .map_or_else(|| self.architecture.name(), |(name, ..)| name.to_string())
+    }
+}
+
+impl From<object::Architecture> for Arch {
+    fn from(architecture: object::Architecture) -> Self {
+        Self { architecture, cpu: None }
+    }
+}
+
+impl fmt::Display for Arch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.name())
+    }
+}
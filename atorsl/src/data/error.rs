@@ -1,5 +1,5 @@
-use super::Addr;
-use std::{ffi, fmt, io, num::ParseIntError, str, string::FromUtf8Error};
+use super::{Addr, Arch};
+use std::{ffi, fmt, io, num::ParseIntError, path::PathBuf, str, string::FromUtf8Error};
 use thiserror::Error;
 
 /// An atorsl error.
@@ -38,6 +38,9 @@ pub enum Error {
     #[error("Address not found: {0}")]
     AddrNotFound(Addr),
 
+    #[error("Address is in no binary image: {0}")]
+    AddrImageNotFound(Addr),
+
     #[error("Address does not point to a symbol: {0}")]
     AddrSymbolMissing(Addr),
 
@@ -62,6 +65,12 @@ pub enum Error {
     #[error("Invalid address: {0}")]
     AddrInvalid(Addr),
 
+    #[error("Invalid address at line {0}: {1}")]
+    AddrInputInvalid(usize, String),
+
+    #[error("Invalid load address: {0}")]
+    LoadAddrInvalid(Addr),
+
     #[error("A string passed had an interior nul byte: {0}")]
     InteriorNul(#[from] ffi::NulError),
 
@@ -74,11 +83,41 @@ pub enum Error {
     #[error("Integer is not a valid address: {0}")]
     UnrepresentableAddress(#[from] ParseIntError),
 
+    #[error("Invalid symbol table in the debug map of the binary image")]
+    DebugMapSymtabInvalid,
+
     #[error("Cannot load symbols")]
     CannotLoadSymbols,
 
-    #[error("Cannot load symbols for architecture {0:?}")]
-    CannotLoadSymbolsForArch(object::Architecture),
+    #[error("Cannot load symbols for architecture {0}, found: {1}")]
+    CannotLoadSymbolsForArch(Arch, String),
+
+    #[error("No DWARF file in {0:?} matches the binary image, found: {1}")]
+    DsymDwarfFileNotFound(PathBuf, String),
+
+    #[error("Several DWARF files in {0:?} match the binary image, found: {1}")]
+    DsymDwarfFileAmbiguous(PathBuf, String),
+
+    #[error("Error reading PDB: {0}")]
+    Pdb(#[from] pdb::Error),
+
+    #[error("PDB does not match the binary image: {0:?}")]
+    PdbMismatch(PathBuf),
+
+    #[error("Invalid Breakpad symbol file at line {0}")]
+    BreakpadSymInvalid(usize),
+
+    #[error("Invalid symbol cache")]
+    SymCacheInvalid,
+
+    #[error("Symbol cache of unsupported version: {0}")]
+    SymCacheVersion(u32),
+
+    #[error("Too much debug info to fit in a symbol cache")]
+    SymCacheTooLarge,
+
+    #[error("Invalid crash report")]
+    CrashReportInvalid,
 
     #[error("Found no UUID in the given object")]
     ObjectHasNoUuid,
@@ -0,0 +1,38 @@
+use super::{Addr, Error};
+
+/// The location address of the binary image containing symbol addresses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Loc {
+    /// The load address of the binary image.
+    ///
+    /// Load addresses for binary images can be found in the Binary Images: section at the
+    /// bottom of the crash, sample, leaks, and malloc_history reports.
+    Load(Addr),
+
+    /// The slide value of the binary image.
+    ///
+    /// This is the difference between the load address of a binary image, and the address at
+    /// which the binary image was built.
+    ///
+    /// This slide value is subtracted from the input addresses.
+    Slide(Addr),
+
+    /// Treat all given addresses as offsets into the binary.
+    Offset,
+}
+
+impl Loc {
+    /// The amount to add to input addresses to translate them into addresses in a binary image
+    /// built at `vmaddr`, only needed for load addresses and offsets.
+    pub fn addr_offset(&self, vmaddr: Option<Addr>) -> Result<i64, Error> {
+        Ok(match self {
+            Loc::Offset => *vmaddr.ok_or(Error::VmAddrTextSegmentNotFound)? as i64,
+            Loc::Slide(slide) => -(**slide as i64),
+            Loc::Load(load_addr) => {
+                -(load_addr
+                    .checked_sub(*vmaddr.ok_or(Error::VmAddrTextSegmentNotFound)?)
+                    .ok_or(Error::LoadAddrInvalid(*load_addr))? as i64)
+            }
+        })
+    }
+}
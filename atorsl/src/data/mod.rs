@@ -1,11 +1,17 @@
 pub mod addr;
+pub mod arch;
 pub mod compilation_unit;
 pub mod error;
+pub mod loc;
 pub mod offset;
 pub mod symbol;
+pub mod symbolication;
 
 pub use addr::Addr;
+pub use arch::Arch;
 pub use compilation_unit::{CompilationUnit, CompilationUnitBuilder};
 pub use error::Error;
+pub use loc::Loc;
 pub use offset::Offset;
-pub use symbol::{SourceLoc, Symbol};
+pub use symbol::{LineRange, SourceLoc, Symbol, SymbolRanges};
+pub use symbolication::{Frames, Source, Symbolication};
@@ -1,6 +1,6 @@
-use super::{Addr, Offset};
+use super::{Addr, Error, Offset, Source};
 use itertools::Either;
-use std::path::PathBuf;
+use std::{ops::Range, path::PathBuf};
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct SourceLoc {
@@ -13,5 +13,45 @@ pub struct SourceLoc {
 pub struct Symbol {
     pub addr: Addr,
     pub name: String,
+
+    /// The name as found in the debug info or symbol table, if it was demangled into `name`.
+    pub mangled_name: Option<String>,
+
     pub loc: Either<SourceLoc, Offset>,
+
+    /// Whether the symbol was inlined into the one following it in its frames.
+    pub inlined: bool,
+}
+
+/// A function found by its name, and the address ranges of its code.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SymbolRanges {
+    pub name: String,
+
+    /// The name as found in the debug info or symbol table, if it was demangled into `name`.
+    pub mangled_name: Option<String>,
+
+    /// The address ranges of the code, empty at the end when found in a symbol table and
+    /// followed by no other symbol to bound it.
+    pub ranges: Vec<Range<Addr>>,
+
+    /// The function the code was inlined into, and the location it was called from, when
+    /// it's an inlined copy.
+    pub inlined_into: Option<(String, SourceLoc)>,
+
+    /// Where the function was found.
+    pub source: Source,
+}
+
+/// A range of code attributed to a source line, and the frames of the code.
+#[derive(Debug)]
+pub struct LineRange {
+    pub range: Range<Addr>,
+
+    /// The location of the first row of the range, with its file as found in the line program.
+    pub loc: SourceLoc,
+
+    /// The function of the code, and the ones inlined into it, the innermost first, or why
+    /// they couldn't be found.
+    pub symbols: Result<Vec<Symbol>, Error>,
 }
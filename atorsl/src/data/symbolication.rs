@@ -0,0 +1,41 @@
+use super::{Addr, Error, Symbol};
+
+/// Where the symbols of an address were found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Source {
+    /// The DWARF debug info of the binary image, with source locations.
+    Dwarf,
+
+    /// The PDB of a PE image, with source locations.
+    Pdb,
+
+    /// The `FUNC` records of a Breakpad symbol file, with source locations.
+    Breakpad,
+
+    /// The symbol table of the binary image, with offsets into its symbols.
+    SymbolTable,
+}
+
+/// The frames symbolicated for an address in a binary image.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Frames {
+    /// Where the symbols were found.
+    pub source: Source,
+
+    /// The symbols of the address, the innermost inlined frame first.
+    pub symbols: Vec<Symbol>,
+}
+
+/// The symbolication of an input address.
+#[derive(Debug)]
+pub struct Symbolication {
+    /// The address as given, before applying the base address of the binary image.
+    pub input_addr: Addr,
+
+    /// The address in the binary image, after applying its base address, unless the result
+    /// would overflow.
+    pub addr: Option<Addr>,
+
+    /// The frames of the address, or why it couldn't be symbolicated.
+    pub result: Result<Frames, Error>,
+}
@@ -0,0 +1,84 @@
+use crate::data::*;
+use memmap2::Mmap;
+use object::Object;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The directory Linux distributions install the separate debug files of their packages to.
+pub const DEFAULT_DEBUG_DIR: &str = "/usr/lib/debug";
+
+/// Finds the separate debug file of the ELF object `obj` at `obj_path`, as `gdb` does.
+///
+/// The debug file is first looked up by the `NT_GNU_BUILD_ID` note of the object, as
+/// `.build-id/xx/yyyyyy.debug` in each of the `debug_dirs`, where `xx` is the first byte of the
+/// build ID in hex and `yyyyyy` the rest of it. It's then looked up by the file name in the
+/// `.gnu_debuglink` section of the object: next to the object, in a `.debug` directory next to
+/// it, and under the object's directory in each of the `debug_dirs`. A debug file is only taken
+/// when its build ID, or the CRC of a debuglink, matches the object's.
+pub fn find(
+    obj: &object::File,
+    obj_path: &Path,
+    debug_dirs: &[PathBuf],
+) -> Result<Option<PathBuf>, Error> {
+    if let Some(build_id) = obj.build_id()?.filter(|build_id| build_id.len() > 1) {
+        let build_id_path = format!(
+            ".build-id/{}/{}.debug",
+            hex(&build_id[..1]),
+            hex(&build_id[1..])
+        );
+
+        for debug_dir in debug_dirs {
+            let path = debug_dir.join(&build_id_path);
+            if path.is_file() && has_build_id(&path, build_id) {
+                return Ok(Some(path));
+            }
+        }
+    }
+
+    let Some((name, crc)) = obj.gnu_debuglink()? else {
+        return Ok(None);
+    };
+
+    let name = String::from_utf8_lossy(name);
+    let name = Path::new(&*name);
+    let obj_dir = fs::canonicalize(obj_path)?
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+
+    Ok([obj_dir.join(name), obj_dir.join(".debug").join(name)]
+        .into_iter()
+        .chain(debug_dirs.iter().map(|debug_dir| {
+            debug_dir
+                .join(obj_dir.strip_prefix("/").unwrap_or(&obj_dir))
+                .join(name)
+        }))
+        .find(|path| path.is_file() && has_crc(path, crc)))
+}
+
+fn has_build_id(path: &Path, build_id: &[u8]) -> bool {
+    map(path).is_some_and(|mmap| {
+        object::File::parse(&*mmap)
+            .ok()
+            .and_then(|obj| obj.build_id().ok().flatten().map(|id| id == build_id))
+            .unwrap_or_default()
+    })
+}
+
+fn has_crc(path: &Path, crc: u32) -> bool {
+    map(path).is_some_and(|mmap| crc32fast::hash(&mmap) == crc)
+}
+
+fn map(path: &Path) -> Option<Mmap> {
+    unsafe { Mmap::map(&fs::File::open(path).ok()?) }.ok()
+}
+
+/// Formats `bytes` in lowercase hex, as build IDs are printed.
+pub fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
@@ -0,0 +1,350 @@
+use crate::{data::*, ext::object::File as _, *};
+use gimli::{EndianSlice, RunTimeEndian};
+use memmap2::Mmap;
+use object::{
+    macho,
+    read::{
+        archive::ArchiveFile,
+        macho::{MachHeader, Nlist},
+        ReadRef, StringTable,
+    },
+    Endianness, Object, ObjectSection, ObjectSegment, ObjectSymbol,
+};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::UNIX_EPOCH,
+};
+
+/// The debug map of a Mach-O binary image that was never run through `dsymutil`, and the
+/// object files it references, mapped in memory for a [`Symbolicator`] to borrow from.
+///
+/// The linker leaves the DWARF of such an image in the object files it was linked from, and
+/// records in the image's symbol table an `N_OSO` stab naming each object file, or archive
+/// member as `libfoo.a(foo.o)`, followed by an `N_FUN` stab with the address and size of each
+/// function linked from it. Addresses are remapped from the image into its object through the
+/// address of the function's symbol in the object, the symbols of each object being read on
+/// the first lookup of an address linked from it.
+#[derive(Debug, Default)]
+pub struct DebugMap {
+    arch: Option<Arch>,
+    files: Vec<(PathBuf, Mmap)>,
+    objects: Vec<Option<DebugMapObject>>,
+    symbols: Vec<DebugMapSymbol>,
+}
+
+/// An object file of the debug map, as the range of its data within a mapped file, and the
+/// addresses of the symbols it defines, by name, once read.
+#[derive(Debug)]
+struct DebugMapObject {
+    file: usize,
+    range: Range<usize>,
+    symbols: OnceLock<HashMap<Vec<u8>, u64>>,
+}
+
+/// A function of the binary image, and the object it was linked from.
+#[derive(Debug)]
+struct DebugMapSymbol {
+    addr: u64,
+    size: u64,
+    object: usize,
+    name: Vec<u8>,
+}
+
+impl DebugMap {
+    /// Maps the object files in the debug map of `obj`, the binary image at `obj_path`.
+    ///
+    /// Each object file is looked up at its path, relative to the directory of the binary
+    /// image when not absolute, as left by `-oso_prefix`, and then by file name next to the
+    /// binary image. Object files that can't be found, or that were modified since they were
+    /// linked, as told by the modification time recorded in the `N_OSO` stab, are skipped,
+    /// leaving the functions linked from them without debug info.
+    pub fn load(obj: &object::File, obj_path: &Path) -> Result<Self, Error> {
+        let object_map = obj.object_map();
+        let mut debug_map = Self {
+            arch: Some(Arch::from(obj.architecture())),
+            ..Default::default()
+        };
+
+        if object_map.objects().is_empty() {
+            return Ok(debug_map);
+        }
+
+        let mtimes = object_mtimes(obj)?;
+        for (index, name) in object_map.objects().iter().enumerate() {
+            let mtime = mtimes.get(index).copied().unwrap_or_default();
+            let object = debug_map.object(&String::from_utf8_lossy(name), obj_path, mtime)?;
+            debug_map.objects.push(object);
+        }
+
+        debug_map.symbols = object_map
+            .symbols()
+            .iter()
+            .map(|entry| DebugMapSymbol {
+                addr: entry.address(),
+                size: entry.size(),
+                object: entry.object_index(),
+                name: entry.name().to_vec(),
+            })
+            .collect();
+
+        Ok(debug_map)
+    }
+
+    /// Finds the object of the function spanning `addr`, and the address `addr` maps to in it.
+    pub(crate) fn find(&self, addr: Addr) -> Result<Option<(usize, Addr)>, Error> {
+        let Some(symbol) = self
+            .symbols
+            .partition_point(|symbol| symbol.addr <= *addr)
+            .checked_sub(1)
+            .map(|index| &self.symbols[index])
+            .filter(|symbol| symbol.size == 0 || *addr - symbol.addr < symbol.size)
+        else {
+            return Ok(None);
+        };
+
+        Ok(self.object_addr(symbol)?.map(|object_addr| {
+            (
+                symbol.object,
+                Addr::from(object_addr + (*addr - symbol.addr)),
+            )
+        }))
+    }
+
+    /// The address and size of each function of the binary image, along with the object it
+    /// was linked from and its address in it.
+    pub(crate) fn symbols(&self) -> Result<Vec<(u64, u64, usize, u64)>, Error> {
+        let mut symbols = Vec::with_capacity(self.symbols.len());
+        for symbol in &self.symbols {
+            if let Some(object_addr) = self.object_addr(symbol)? {
+                symbols.push((symbol.addr, symbol.size, symbol.object, object_addr));
+            }
+        }
+
+        Ok(symbols)
+    }
+
+    /// The number of objects in the debug map, including the ones that couldn't be found.
+    pub(crate) fn objects_len(&self) -> usize {
+        self.objects.len()
+    }
+
+    /// Loads the DWARF of the object at `index`, if it was found.
+    pub(crate) fn object_dwarf(&self, index: usize) -> Result<Option<Dwarf<'_>>, Error> {
+        let Some(Some(object)) = self.objects.get(index) else {
+            return Ok(None);
+        };
+
+        let obj = self.parse(object)?;
+        let endian = if obj.is_little_endian() {
+            RunTimeEndian::Little
+        } else {
+            RunTimeEndian::Big
+        };
+
+        Ok(Some(gimli::Dwarf::load(
+            |section_id| -> Result<_, Error> {
+                Ok(EndianSlice::new(
+                    obj.section_by_name(section_id.name())
+                        .and_then(|section| match section.uncompressed_data().ok()? {
+                            Cow::Borrowed(data) => Some(data),
+                            Cow::Owned(_) => None,
+                        })
+                        .unwrap_or_default(),
+                    endian,
+                ))
+            },
+        )?))
+    }
+
+    /// The address of `symbol` in the object it was linked from, reading the symbols of the
+    /// object on first use.
+    fn object_addr(&self, symbol: &DebugMapSymbol) -> Result<Option<u64>, Error> {
+        let Some(Some(object)) = self.objects.get(symbol.object) else {
+            return Ok(None);
+        };
+
+        if let Some(symbols) = object.symbols.get() {
+            return Ok(symbols.get(&symbol.name).copied());
+        }
+
+        let symbols = self.object_symbols(object)?;
+        Ok(object
+            .symbols
+            .get_or_init(|| symbols)
+            .get(&symbol.name)
+            .copied())
+    }
+
+    /// Finds and maps the object file, or archive member, named `name` in the debug map, unless
+    /// it was modified since `mtime`, the time it was linked at, if known.
+    fn object(
+        &mut self,
+        name: &str,
+        obj_path: &Path,
+        mtime: u64,
+    ) -> Result<Option<DebugMapObject>, Error> {
+        let (path, member) = match name
+            .strip_suffix(')')
+            .and_then(|name| name.rsplit_once('('))
+        {
+            Some((path, member)) => (Path::new(path), Some(member)),
+            None => (Path::new(name), None),
+        };
+
+        let obj_dir = obj_path.parent().unwrap_or(Path::new(""));
+        let Some(path) = [obj_dir.join(path)]
+            .into_iter()
+            .chain(
+                path.file_name()
+                    .map(|file_name| obj_dir.join(file_name)),
+            )
+            .find(|path| path.is_file())
+        else {
+            return Ok(None);
+        };
+
+        if member.is_none() && mtime != 0 && modified_time(&path)? != Some(mtime) {
+            return Ok(None);
+        }
+
+        let file = match self.files.iter().position(|(file, _)| *file == path) {
+            Some(file) => file,
+            None => {
+                let mmap = unsafe { Mmap::map(&fs::File::open(&path)?) }?;
+                self.files.push((path, mmap));
+                self.files.len() - 1
+            }
+        };
+
+        let data = &self.files[file].1;
+        let range = match member {
+            Some(member) => {
+                let Some(member) = ArchiveFile::parse(&**data)?
+                    .members()
+                    .filter_map(Result::ok)
+                    .find(|archive_member| archive_member.name() == member.as_bytes())
+                else {
+                    return Ok(None);
+                };
+
+                if mtime != 0 && member.date() != Some(mtime) {
+                    return Ok(None);
+                }
+
+                let (offset, size) = member.file_range();
+                offset as usize..(offset + size) as usize
+            }
+            None => 0..data.len(),
+        };
+
+        Ok(Some(DebugMapObject {
+            file,
+            range,
+            symbols: OnceLock::new(),
+        }))
+    }
+
+    /// The addresses of the symbols defined in `object`, by name.
+    fn object_symbols(&self, object: &DebugMapObject) -> Result<HashMap<Vec<u8>, u64>, Error> {
+        Ok(self
+            .parse(object)?
+            .symbols()
+            .filter(|symbol| symbol.is_definition())
+            .filter_map(|symbol| Some((symbol.name_bytes().ok()?.to_vec(), symbol.address())))
+            .collect())
+    }
+
+    fn parse(&self, object: &DebugMapObject) -> Result<object::File<'_>, Error> {
+        let data = self.files[object.file]
+            .1
+            .get(object.range.clone())
+            .ok_or(Error::CannotLoadSymbols)?;
+
+        object::File::parse_data(data, self.arch)
+    }
+}
+
+/// The modification time of the file at `path`, in seconds since the epoch.
+fn modified_time(path: &Path) -> Result<Option<u64>, Error> {
+    Ok(fs::metadata(path)?
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs()))
+}
+
+/// The modification times recorded in the `N_OSO` stabs of `obj`, in the order of the objects
+/// of its debug map, 0 standing for an unknown time.
+///
+/// The high-level API of `object` skips stabs, so they're read from the symbol table in the
+/// `__LINKEDIT` segment, found through the load commands at the start of `__TEXT`.
+fn object_mtimes(obj: &object::File) -> Result<Vec<u64>, Error> {
+    fn macho<Mach: MachHeader<Endian = Endianness>>(
+        text: &[u8],
+        linkedit: &[u8],
+        linkedit_offset: u64,
+    ) -> Result<Vec<u64>, Error> {
+        let header = Mach::parse(text, 0)?;
+        let endian = header.endian()?;
+        let mut commands = header.load_commands(endian, text, 0)?;
+
+        while let Some(command) = commands.next()? {
+            let Some(symtab) = command.symtab()? else {
+                continue;
+            };
+
+            let offset = |file_offset: u32| {
+                u64::from(file_offset)
+                    .checked_sub(linkedit_offset)
+                    .ok_or(Error::DebugMapSymtabInvalid)
+            };
+
+            let symbols_offset = offset(symtab.symoff.get(endian))?;
+            let symbols = linkedit
+                .read_slice_at::<Mach::Nlist>(symbols_offset, symtab.nsyms.get(endian) as usize)
+                .map_err(|()| Error::DebugMapSymtabInvalid)?;
+
+            let strings_offset = offset(symtab.stroff.get(endian))?;
+            let strings = StringTable::new(
+                linkedit,
+                strings_offset,
+                strings_offset + u64::from(symtab.strsize.get(endian)),
+            );
+
+            return Ok(symbols
+                .iter()
+                .filter(|nlist| nlist.n_type() == macho::N_OSO)
+                .filter(|nlist| {
+                    nlist
+                        .name(endian, strings)
+                        .is_ok_and(|name| !name.is_empty())
+                })
+                .map(|nlist| nlist.n_value(endian).into())
+                .collect());
+        }
+
+        Ok(Vec::default())
+    }
+
+    let segment = |name| {
+        obj.segments()
+            .find(|segment| segment.name().ok().flatten() == Some(name))
+    };
+
+    let (Some(text), Some(linkedit)) = (segment("__TEXT"), segment("__LINKEDIT")) else {
+        return Ok(Vec::default());
+    };
+
+    let (text, linkedit_offset, linkedit) =
+        (text.data()?, linkedit.file_range().0, linkedit.data()?);
+
+    match obj.is_64() {
+        true => macho::<macho::MachHeader64<Endianness>>(text, linkedit, linkedit_offset),
+        false => macho::<macho::MachHeader32<Endianness>>(text, linkedit, linkedit_offset),
+    }
+}
@@ -0,0 +1,162 @@
+use crate::{data::*, ext::object::File as _};
+use memmap2::Mmap;
+use object::Object;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// A `.dSYM` bundle, and the DWARF files in its `Contents/Resources/DWARF`.
+///
+/// A bundle usually has a single DWARF file, named as the binary image it was generated from,
+/// but the dSYM of a framework may have one for each binary image in it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dsym {
+    path: PathBuf,
+    dwarf_files: Vec<DsymDwarfFile>,
+}
+
+/// A DWARF file of a `.dSYM` bundle, and the UUID of each of its architectures.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DsymDwarfFile {
+    pub path: PathBuf,
+    pub slices: Vec<(Option<[u8; 16]>, Arch)>,
+}
+
+impl Dsym {
+    /// Whether `path` names a `.dSYM` bundle.
+    pub fn is_dsym(path: &Path) -> bool {
+        path.extension().is_some_and(|ext| ext == "dSYM")
+    }
+
+    /// Opens the `.dSYM` bundle at `path`, and reads the architectures of its DWARF files, in
+    /// alphabetical order.
+    ///
+    /// Entries that aren't files or don't parse as binary images, as a stray `.DS_Store`, are
+    /// skipped.
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let mut paths = fs::read_dir(path.join("Contents/Resources/DWARF"))?
+            .map(|entry| Ok(entry?.path()))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        paths.sort();
+
+        let mut dwarf_files = Vec::with_capacity(paths.len());
+        for path in paths.into_iter().filter(|path| path.is_file()) {
+            let mmap = unsafe { Mmap::map(&fs::File::open(&path)?) }?;
+            let Ok(slices) = object::File::parse_slices(&mmap) else {
+                continue;
+            };
+
+            dwarf_files.push(DsymDwarfFile {
+                path,
+                slices: slices
+                    .iter()
+                    .map(|(arch, obj)| (obj.mach_uuid().ok().flatten(), *arch))
+                    .collect(),
+            });
+        }
+
+        if dwarf_files.is_empty() {
+            return Err(Error::DsymDwarfFileNotFound(
+                path.to_path_buf(),
+                String::from("none"),
+            ));
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            dwarf_files,
+        })
+    }
+
+    /// The DWARF files of the bundle, in alphabetical order.
+    pub fn dwarf_files(&self) -> &[DsymDwarfFile] {
+        &self.dwarf_files
+    }
+
+    /// Selects the DWARF file with `uuid` and `arch`, when given.
+    ///
+    /// When several DWARF files match, the one named as the binary image the bundle is named
+    /// after is selected, as `Foo` in `Foo.framework.dSYM` or `libfoo.dylib` in
+    /// `libfoo.dylib.dSYM`. Fails listing the candidates when none or several of them match.
+    pub fn select(&self, uuid: Option<&[u8; 16]>, arch: Option<Arch>) -> Result<&Path, Error> {
+        let matches = self
+            .dwarf_files
+            .iter()
+            .filter(|dwarf_file| {
+                dwarf_file
+                    .slices
+                    .iter()
+                    .any(|(slice_uuid, slice_arch)| {
+                        uuid.map_or(true, |uuid| slice_uuid.as_ref() == Some(uuid))
+                            && arch.map_or(true, |arch| arch.matches(slice_arch))
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        let selected = match matches[..] {
+            [dwarf_file] => Some(dwarf_file),
+            [] => {
+                return Err(Error::DsymDwarfFileNotFound(
+                    self.path.clone(),
+                    self.candidates(),
+                ))
+            }
+            _ => {
+                let bundle_name = self.path.file_stem().unwrap_or_default();
+                let image_name = Path::new(bundle_name).file_stem().unwrap_or_default();
+
+                let mut named = matches.iter().filter(|dwarf_file| {
+                    dwarf_file
+                        .path
+                        .file_name()
+                        .is_some_and(|name| name == bundle_name || name == image_name)
+                });
+
+                named
+                    .next()
+                    .filter(|_| named.next().is_none())
+                    .copied()
+            }
+        };
+
+        selected
+            .map(|dwarf_file| dwarf_file.path.as_path())
+            .ok_or_else(|| Error::DsymDwarfFileAmbiguous(self.path.clone(), self.candidates()))
+    }
+
+    /// The DWARF files of the bundle, with the UUID and architecture of each of their slices.
+    fn candidates(&self) -> String {
+        self.dwarf_files
+            .iter()
+            .flat_map(|dwarf_file| {
+                dwarf_file.slices.iter().map(|(uuid, arch)| {
+                    format!(
+                        "{} ({} {})",
+                        dwarf_file
+                            .path
+                            .file_name()
+                            .unwrap_or_default()
+                            .to_string_lossy(),
+                        uuid.map(|uuid| format_uuid(&uuid))
+                            .unwrap_or_else(|| String::from("no UUID")),
+                        arch.name()
+                    )
+                })
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Formats `uuid` as `symbols -uuid` prints it.
+fn format_uuid(uuid: &[u8; 16]) -> String {
+    uuid.iter()
+        .enumerate()
+        .map(|(index, byte)| match index {
+            4 | 6 | 8 | 10 => format!("-{byte:02X}"),
+            _ => format!("{byte:02X}"),
+        })
+        .collect()
+}
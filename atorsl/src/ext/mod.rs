@@ -1,64 +1,201 @@
 pub mod object {
-    use crate::data::{Addr, Error};
+    use crate::data::{Addr, Arch, Error};
     use object::{
         macho,
-        read::{self, macho::FatArch},
-        Object, ObjectSegment,
+        read::macho::{FatArch, MachHeader},
+        Endianness, Object, ObjectSegment,
     };
+    use std::ops::Range;
 
     pub trait File {
-        fn parse_data(
+        fn parse_data(data: &[u8], selected_arch: Option<Arch>)
+            -> Result<object::File<'_>, Error>;
+
+        fn parse_slice(
             data: &[u8],
-            selected_arch: Option<object::Architecture>,
-        ) -> Result<object::File, Error>;
+            selected_arch: Option<Arch>,
+        ) -> Result<(Arch, object::File<'_>), Error>;
 
         fn parse_macho<'a, T: FatArch>(
             data: &'a [u8],
             fat_arches: &[T],
-            selected_arch: Option<object::Architecture>,
-        ) -> Result<object::File<'a>, Error>;
+            selected_arch: Option<Arch>,
+        ) -> Result<(Arch, object::File<'a>), Error>;
+
+        fn parse_slices(data: &[u8]) -> Result<Vec<(Arch, object::File<'_>)>, Error>;
 
         fn vmaddr(&self) -> Result<Addr, Error>;
+
+        fn text_range(&self) -> Result<Range<u64>, Error>;
     }
 
     impl File for object::File<'_> {
         fn parse_data(
             data: &[u8],
-            selected_arch: Option<object::Architecture>,
-        ) -> Result<object::File, Error> {
+            selected_arch: Option<Arch>,
+        ) -> Result<object::File<'_>, Error> {
+            object::File::parse_slice(data, selected_arch).map(|(_, obj)| obj)
+        }
+
+        /// Parses the architecture `selected_arch` of a universal binary, or its first one, or
+        /// the single one of any other object, along with the architecture parsed.
+        fn parse_slice(
+            data: &[u8],
+            selected_arch: Option<Arch>,
+        ) -> Result<(Arch, object::File<'_>), Error> {
             if let Ok(fat_arches) = macho::FatHeader::parse_arch32(data) {
                 object::File::parse_macho(data, fat_arches, selected_arch)
             } else if let Ok(fat_arches) = macho::FatHeader::parse_arch64(data) {
                 object::File::parse_macho(data, fat_arches, selected_arch)
             } else {
-                Ok(object::File::parse(data)?)
+                let obj = object::File::parse(data)?;
+                Ok((arch(data, &obj), obj))
             }
         }
 
+        /// Parses the slice of `fat_arches` that is of `selected_arch`, or else the first one
+        /// `selected_arch` matches, as `arm64` does an `arm64e` slice. Fails listing the
+        /// architectures of the slices when none matches.
         fn parse_macho<'a, T: FatArch>(
             data: &'a [u8],
             fat_arches: &[T],
-            selected_arch: Option<object::Architecture>,
-        ) -> Result<object::File<'a>, Error> {
-            Ok(object::File::parse(read::macho::FatArch::data(
-                if let Some(selected_arch) = selected_arch {
-                    fat_arches
-                        .iter()
-                        .find(|fat_arch| selected_arch == fat_arch.architecture())
-                        .ok_or(Error::CannotLoadSymbolsForArch(selected_arch))?
-                } else {
-                    fat_arches.first().ok_or(Error::CannotLoadSymbols)?
-                },
-                data,
-            )?)?)
+            selected_arch: Option<Arch>,
+        ) -> Result<(Arch, object::File<'a>), Error> {
+            let archs = fat_arches
+                .iter()
+                .map(|fat_arch| {
+                    Arch::from_macho(
+                        fat_arch.architecture(),
+                        fat_arch.cputype(),
+                        fat_arch.cpusubtype(),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            let index = if let Some(selected_arch) = selected_arch {
+                archs
+                    .iter()
+                    .position(|arch| *arch == selected_arch)
+                    .or_else(|| {
+                        archs
+                            .iter()
+                            .position(|arch| selected_arch.matches(arch))
+                    })
+                    .ok_or_else(|| {
+                        Error::CannotLoadSymbolsForArch(
+                            selected_arch,
+                            archs
+                                .iter()
+                                .map(Arch::name)
+                                .collect::<Vec<_>>()
+                                .join(", "),
+                        )
+                    })?
+            } else if fat_arches.is_empty() {
+                return Err(Error::CannotLoadSymbols);
+            } else {
+                0
+            };
+
+            Ok((
+                archs[index],
+                object::File::parse(fat_arches[index].data(data)?)?,
+            ))
         }
 
+        /// Parses every architecture of a universal binary, or the single one of any other
+        /// object, along with the architecture of each.
+        fn parse_slices(data: &[u8]) -> Result<Vec<(Arch, object::File<'_>)>, Error> {
+            fn parse<'a, T: FatArch>(
+                data: &'a [u8],
+                fat_arches: &[T],
+            ) -> Result<Vec<(Arch, object::File<'a>)>, Error> {
+                fat_arches
+                    .iter()
+                    .map(|fat_arch| {
+                        Ok((
+                            Arch::from_macho(
+                                fat_arch.architecture(),
+                                fat_arch.cputype(),
+                                fat_arch.cpusubtype(),
+                            ),
+                            object::File::parse(fat_arch.data(data)?)?,
+                        ))
+                    })
+                    .collect()
+            }
+
+            if let Ok(fat_arches) = macho::FatHeader::parse_arch32(data) {
+                parse(data, fat_arches)
+            } else if let Ok(fat_arches) = macho::FatHeader::parse_arch64(data) {
+                parse(data, fat_arches)
+            } else {
+                let obj = object::File::parse(data)?;
+                Ok(vec![(arch(data, &obj), obj)])
+            }
+        }
+
+        /// The address the binary image was built at: the address of its `__TEXT` segment, its
+        /// image base for a PE, or the address of its lowest loaded segment for an ELF.
         fn vmaddr(&self) -> Result<Addr, Error> {
+            match self.format() {
+                object::BinaryFormat::Pe => return Ok(Addr::from(self.relative_address_base())),
+                object::BinaryFormat::Elf => {
+                    return self
+                        .segments()
+                        .map(|seg| seg.address())
+                        .min()
+                        .ok_or(Error::VmAddrTextSegmentNotFound)
+                        .map(Addr::from)
+                }
+                _ => {}
+            }
+
             self.segments()
                 .find_map(|seg| seg.name().ok()??.eq("__TEXT").then(|| seg.address()))
                 .ok_or(Error::VmAddrTextSegmentNotFound)
                 .map(Addr::from)
         }
+
+        /// The address range of the `__TEXT` segment, or the range spanning every segment of
+        /// objects without one.
+        fn text_range(&self) -> Result<Range<u64>, Error> {
+            let range = |seg: &object::Segment| seg.address()..seg.address() + seg.size();
+
+            if let Some(text) = self
+                .segments()
+                .find(|seg| seg.name().ok().flatten() == Some("__TEXT"))
+            {
+                return Ok(range(&text));
+            }
+
+            self.segments()
+                .map(|seg| range(&seg))
+                .reduce(|span, range| span.start.min(range.start)..span.end.max(range.end))
+                .ok_or(Error::VmAddrTextSegmentNotFound)
+        }
+    }
+
+    /// The architecture of `obj`, parsed from `data`, down to its CPU subtype when it's a
+    /// Mach-O.
+    fn arch(data: &[u8], obj: &object::File) -> Arch {
+        fn macho<Mach: MachHeader>(data: &[u8], obj: &object::File) -> Option<Arch> {
+            let header = Mach::parse(data, 0).ok()?;
+            let endian = header.endian().ok()?;
+
+            Some(Arch::from_macho(
+                obj.architecture(),
+                header.cputype(endian),
+                header.cpusubtype(endian),
+            ))
+        }
+
+        match object::FileKind::parse(data) {
+            Ok(object::FileKind::MachO32) => macho::<macho::MachHeader32<Endianness>>(data, obj),
+            Ok(object::FileKind::MachO64) => macho::<macho::MachHeader64<Endianness>>(data, obj),
+            _ => None,
+        }
+        .unwrap_or_else(|| Arch::from(obj.architecture()))
     }
 
     pub trait Architecture {
@@ -87,8 +224,8 @@ pub mod object {
                 "mips" => Self::Mips,
                 "mips64" => Self::Mips64,
                 "msp430" => Self::Msp430,
-                "powerpc" => Self::PowerPc,
-                "powerpc64" => Self::PowerPc64,
+                "powerpc" | "ppc" => Self::PowerPc,
+                "powerpc64" | "ppc64" => Self::PowerPc64,
                 "riscv32" => Self::Riscv32,
                 "riscv64" => Self::Riscv64,
                 "s390x" => Self::S390x,
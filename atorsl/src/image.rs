@@ -0,0 +1,279 @@
+use crate::{
+    data::*,
+    ext::object::File as _,
+    pdb_file::PdbFile,
+    symbol_query::SymbolQuery,
+    symbolicator::{atos_map, demangle, Symbolicator},
+};
+use object::{Object, SymbolMap, SymbolMapName};
+use std::{
+    num::NonZeroUsize,
+    panic,
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+/// The number of addresses a thread symbolicates at a time in [`Image::symbolicate_parallel`].
+const PARALLEL_CHUNK_LEN: usize = 256;
+
+/// A binary image to symbolicate addresses against.
+///
+/// Addresses are symbolicated against the DWARF indexed by the [`Symbolicator`], falling back
+/// to the symbol table of the binary image for addresses without debug info, as `atos` does.
+/// The PDB of a PE image, if given one [`with_pdb`](Self::with_pdb), is looked up before the
+/// DWARF.
+pub struct Image<'data> {
+    symbolicator: Symbolicator<'data>,
+    pdb: Option<&'data PdbFile>,
+    symbol_map: SymbolMap<SymbolMapName<'data>>,
+    vmaddr: Option<Addr>,
+}
+
+impl<'data> Image<'data> {
+    /// The binary image `obj`, with its DWARF indexed by `symbolicator`.
+    pub fn new(obj: &object::File<'data>, symbolicator: Symbolicator<'data>) -> Self {
+        Self {
+            symbolicator,
+            pdb: None,
+            symbol_map: obj.symbol_map(),
+            vmaddr: obj.vmaddr().ok(),
+        }
+    }
+
+    /// Looks up addresses in `pdb`, the PDB of the binary image, before its DWARF.
+    pub fn with_pdb(mut self, pdb: &'data PdbFile) -> Self {
+        self.pdb = Some(pdb);
+        self
+    }
+
+    /// Symbolicates `addrs`, given relative to `base_addr`, in order.
+    ///
+    /// Fails only when `base_addr` can't be applied to the binary image, errors symbolicating
+    /// each address are returned along with it.
+    pub fn symbolicate<I>(
+        &self,
+        base_addr: &Loc,
+        addrs: I,
+        include_inlined: bool,
+    ) -> Result<Symbolications<'_, 'data, I::IntoIter>, Error>
+    where
+        I: IntoIterator<Item = Addr>,
+    {
+        Ok(Symbolications {
+            image: self,
+            addrs: addrs.into_iter(),
+            addr_offset: base_addr.addr_offset(self.vmaddr)?,
+            include_inlined,
+        })
+    }
+
+    /// Symbolicates `addrs`, given relative to `base_addr`, across `threads` threads.
+    ///
+    /// Threads take chunks of consecutive addresses as they finish the previous ones, and the
+    /// symbolications are returned in the order of `addrs`, as [`symbolicate`] would return
+    /// them.
+    ///
+    /// [`symbolicate`]: Self::symbolicate
+    pub fn symbolicate_parallel(
+        &self,
+        base_addr: &Loc,
+        addrs: &[Addr],
+        include_inlined: bool,
+        threads: NonZeroUsize,
+    ) -> Result<Vec<Symbolication>, Error> {
+        let addr_offset = base_addr.addr_offset(self.vmaddr)?;
+        let chunks = addrs.chunks(PARALLEL_CHUNK_LEN).collect::<Vec<_>>();
+        let next_chunk = AtomicUsize::new(0);
+
+        let mut symbolicated_chunks = thread::scope(|scope| {
+            let workers = (0..threads.get().min(chunks.len()))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut symbolicated_chunks = Vec::default();
+                        loop {
+                            let index = next_chunk.fetch_add(1, Ordering::Relaxed);
+                            let Some(chunk) = chunks.get(index) else {
+                                break symbolicated_chunks;
+                            };
+
+                            symbolicated_chunks.push((
+                                index,
+                                Symbolications {
+                                    image: self,
+                                    addrs: chunk.iter().copied(),
+                                    addr_offset,
+                                    include_inlined,
+                                }
+                                .collect::<Vec<_>>(),
+                            ));
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            workers
+                .into_iter()
+                .flat_map(|worker| {
+                    worker
+                        .join()
+                        .unwrap_or_else(|err| panic::resume_unwind(err))
+                })
+                .collect::<Vec<_>>()
+        });
+
+        symbolicated_chunks.sort_unstable_by_key(|(index, _)| *index);
+
+        Ok(symbolicated_chunks
+            .into_iter()
+            .flat_map(|(_, symbolications)| symbolications)
+            .collect())
+    }
+
+    /// Looks up the code of the functions matching `query`, every inlined copy included, with
+    /// addresses relative to `base_addr`, as the addresses given to [`symbolicate`] are.
+    ///
+    /// Functions are looked up in the DWARF, then in the symbol table, where symbols starting
+    /// where a function of the DWARF does are left out.
+    ///
+    /// [`symbolicate`]: Self::symbolicate
+    pub fn lookup_symbol(
+        &self,
+        base_addr: &Loc,
+        query: &SymbolQuery,
+    ) -> Result<Vec<SymbolRanges>, Error> {
+        let addr_offset = base_addr.addr_offset(self.vmaddr)?;
+        let mut found = self.symbolicator.lookup_symbol(query)?;
+
+        let symbols = self.symbol_map.symbols();
+        for (index, symbol) in symbols.iter().enumerate() {
+            let symbol_name = symbol.name();
+            let (name, mangled_name) = demangle(
+                symbol_name
+                    .strip_prefix('_')
+                    .unwrap_or(symbol_name)
+                    .to_owned(),
+            );
+
+            let addr = Addr::from(symbol.address());
+            if !query.matches(&name, mangled_name.as_deref())
+                || found.iter().any(|found| {
+                    found
+                        .ranges
+                        .first()
+                        .is_some_and(|range| range.start == addr)
+                })
+            {
+                continue;
+            }
+
+            let end = symbols
+                .get(index + 1)
+                .map_or(addr, |next| Addr::from(next.address()));
+
+            found.push(SymbolRanges {
+                name,
+                mangled_name,
+                ranges: vec![addr..end],
+                inlined_into: None,
+                source: Source::SymbolTable,
+            });
+        }
+
+        let unslide = |addr: Addr| Addr::from(addr.wrapping_add_signed(-addr_offset));
+        for found in &mut found {
+            for range in &mut found.ranges {
+                *range = unslide(range.start)..unslide(range.end);
+            }
+        }
+
+        Ok(found)
+    }
+
+    /// Looks up the ranges of code the DWARF attributes to `line` of `file`, a path matching
+    /// the last components of the source files, along with the frames of each range, with
+    /// addresses relative to `base_addr`, as the addresses given to [`symbolicate`] are.
+    ///
+    /// [`symbolicate`]: Self::symbolicate
+    pub fn lookup_line(
+        &self,
+        base_addr: &Loc,
+        file: &Path,
+        line: u64,
+    ) -> Result<Vec<LineRange>, Error> {
+        let addr_offset = base_addr.addr_offset(self.vmaddr)?;
+        let unslide = |addr: Addr| Addr::from(addr.wrapping_add_signed(-addr_offset));
+
+        let mut found = self.symbolicator.lookup_line(file, line)?;
+        for found in &mut found {
+            found.range = unslide(found.range.start)..unslide(found.range.end);
+            found
+                .symbols
+                .iter_mut()
+                .flatten()
+                .for_each(|symbol| symbol.addr = unslide(symbol.addr));
+        }
+
+        Ok(found)
+    }
+
+    /// Symbolicates `addr`, an address in the binary image.
+    pub fn atos(&self, addr: Addr, include_inlined: bool) -> Result<Frames, Error> {
+        if let Some(pdb) = self.pdb {
+            match pdb.atos(addr, include_inlined) {
+                Ok(symbols) => return Ok(Frames { source: Source::Pdb, symbols }),
+                Err(Error::AddrNotFound(_)) => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        match self.symbolicator.atos(addr, include_inlined) {
+            Ok(symbols) => Ok(Frames { source: Source::Dwarf, symbols }),
+
+            Err(
+                Error::AddrNotFound(_)
+                | Error::AddrDebugInfoOffsetMissing(_)
+                | Error::AddrLineInfoMissing(_),
+            ) => Ok(Frames {
+                source: Source::SymbolTable,
+                symbols: atos_map(&self.symbol_map, addr)?,
+            }),
+
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// An iterator over the [`Symbolication`] of each address given to [`Image::symbolicate`].
+pub struct Symbolications<'a, 'data, I> {
+    image: &'a Image<'data>,
+    addrs: I,
+    addr_offset: i64,
+    include_inlined: bool,
+}
+
+impl<I> Iterator for Symbolications<'_, '_, I>
+where
+    I: Iterator<Item = Addr>,
+{
+    type Item = Symbolication;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let input_addr = self.addrs.next()?;
+        let addr = input_addr
+            .checked_add_signed(self.addr_offset)
+            .map(Addr::from);
+
+        Some(Symbolication {
+            input_addr,
+            addr,
+            result: addr
+                .ok_or(Error::AddrInvalid(input_addr))
+                .and_then(|addr| self.image.atos(addr, self.include_inlined)),
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.addrs.size_hint()
+    }
+}
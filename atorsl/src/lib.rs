@@ -1,10 +1,37 @@
+pub mod addr_input;
+pub mod backtrace;
+pub mod breakpad;
+pub mod crash_report;
 pub mod data;
+pub mod debug_file;
+pub mod debug_map;
 pub mod demangler;
+pub mod dsym;
 pub mod ext;
+pub mod image;
+pub mod pdb_file;
+pub mod split_dwarf;
+pub mod symbol_query;
 pub mod symbolicator;
+pub mod symcache;
+pub mod uuid_index;
 
+mod line_table;
+
+pub use addr_input::AddrInput;
+pub use backtrace::BacktraceFrame;
+pub use breakpad::{BreakpadDump, BreakpadModule, BreakpadSym};
+pub use crash_report::{CrashImage, CrashReport};
 pub use data::Error;
-pub use symbolicator::{atos_dwarf, atos_map};
+pub use debug_map::DebugMap;
+pub use dsym::{Dsym, DsymDwarfFile};
+pub use image::{Image, Symbolications};
+pub use pdb_file::PdbFile;
+pub use split_dwarf::SplitDwarf;
+pub use symbol_query::SymbolQuery;
+pub use symbolicator::{atos_dwarf, atos_map, Symbolicator};
+pub use symcache::SymCache;
+pub use uuid_index::{IndexedImage, UuidIndex};
 
 /// Loads a binary image object as DWARF.
 #[macro_export]
@@ -31,6 +58,43 @@ macro_rules! load_dwarf {
     }};
 }
 
+/// The DWARF sections of a binary image object, decompressed if needed, for a [`Dwarf`] to
+/// borrow from.
+///
+/// Unlike the binding of [`load_dwarf!`], the sections can be kept in a collection, to load
+/// the DWARF of several binary images at once.
+pub struct DwarfSections<'data> {
+    sections: gimli::Dwarf<std::borrow::Cow<'data, [u8]>>,
+    endian: gimli::RunTimeEndian,
+}
+
+impl<'data> DwarfSections<'data> {
+    /// Loads the DWARF sections of `obj`.
+    pub fn load(obj: &object::File<'data>) -> Result<Self, Error> {
+        use object::{Object, ObjectSection};
+
+        Ok(Self {
+            sections: gimli::Dwarf::load(|section_id| -> Result<_, Error> {
+                Ok(obj
+                    .section_by_name(section_id.name())
+                    .and_then(|section| section.uncompressed_data().ok())
+                    .unwrap_or_default())
+            })?,
+            endian: if obj.is_little_endian() {
+                gimli::RunTimeEndian::Little
+            } else {
+                gimli::RunTimeEndian::Big
+            },
+        })
+    }
+
+    /// The DWARF borrowing these sections.
+    pub fn dwarf(&self) -> Dwarf<'_> {
+        self.sections
+            .borrow(|section| gimli::EndianSlice::new(section, self.endian))
+    }
+}
+
 /// Commonly used DWARF sections, and other common information.
 pub type Dwarf<'input> = gimli::Dwarf<gimli::EndianSlice<'input, gimli::RunTimeEndian>>;
 
@@ -52,16 +116,13 @@ pub(crate) type Entry<'abbrev, 'unit, 'input> = gimli::DebuggingInformationEntry
     usize,
 >;
 
-/// Executes a `LineProgram` to iterate over the rows in the matrix of line number information.
+/// A line number program that has not been run to completion.
 ///
 /// "The hypothetical machine used by a consumer of the line number information
 /// to expand the byte-coded instruction stream into a matrix of line number
 /// information." -- Section 6.2.1
-pub(crate) type IncompleteLineProgramRows<'input> = gimli::LineRows<
-    gimli::EndianSlice<'input, gimli::RunTimeEndian>,
-    gimli::IncompleteLineProgram<gimli::EndianSlice<'input, gimli::RunTimeEndian>, usize>,
-    usize,
->;
+pub(crate) type IncompleteLineProgram<'input> =
+    gimli::IncompleteLineProgram<gimli::EndianSlice<'input, gimli::RunTimeEndian>, usize>;
 
 /// A header for a line number program in the `.debug_line` section, as defined
 /// in section 6.2.4 of the standard.
@@ -0,0 +1,139 @@
+use crate::{data::*, symbolicator::DwarfExt, *};
+use gimli::ColumnType;
+use std::{
+    mem,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+/// A row in the line number matrix of a compilation unit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct LineRow {
+    pub addr: u64,
+    pub file: u64,
+    pub line: u64,
+    pub col: u64,
+    pub end_sequence: bool,
+}
+
+/// The line program of a compilation unit, decoded once into rows sorted by address.
+///
+/// Replaying a line program is linear in its size, so the rows of every sequence are kept,
+/// sorted by the address each sequence starts at, for all the lookups in the unit to be a
+/// binary search. A row spans from its address up to the address of the next row, unless it
+/// ends its sequence.
+pub(crate) struct LineTable {
+    rows: Vec<LineRow>,
+    files: Vec<Option<PathBuf>>,
+}
+
+impl LineTable {
+    /// Decodes the line `program` of `unit`.
+    pub fn new(dwarf: &Dwarf, unit: &Unit, program: IncompleteLineProgram) -> Result<Self, Error> {
+        let files = (0..=program.header().file_names().len() as u64)
+            .map(|index| {
+                let header = program.header();
+                let file = header.file(index)?;
+                let dir = match file.directory(header) {
+                    Some(dir) if file.directory_index() != 0 => {
+                        PathBuf::from(&*dwarf.attr_lossy_string(unit, dir).ok()?)
+                    }
+                    _ => PathBuf::default(),
+                };
+
+                Some(dir.join(&*dwarf.attr_lossy_string(unit, file.path_name()).ok()?))
+            })
+            .collect();
+
+        let mut sequences = Vec::default();
+        let mut sequence = Vec::default();
+
+        let mut line_rows = program.rows();
+        while let Some((_, row)) = line_rows.next_row()? {
+            sequence.push(LineRow {
+                addr: row.address(),
+                file: row.file_index(),
+                line: row.line().map(|line| line.get()).unwrap_or_default(),
+                col: match row.column() {
+                    ColumnType::LeftEdge => 0,
+                    ColumnType::Column(col) => col.get(),
+                },
+                end_sequence: row.end_sequence(),
+            });
+
+            if row.end_sequence() {
+                sequences.push(mem::take(&mut sequence));
+            }
+        }
+
+        // An unterminated sequence still spans up to its last row.
+        if let Some(last) = sequence.last_mut() {
+            last.end_sequence = true;
+            sequences.push(sequence);
+        }
+
+        sequences.sort_by_key(|sequence| sequence[0].addr);
+
+        // Sequences only overlap when the linker discarded the code they describe and left
+        // them at a tombstone address, keep the first one found to keep the rows sorted.
+        let mut rows = Vec::<LineRow>::default();
+        for sequence in sequences {
+            if rows
+                .last()
+                .is_some_and(|last| sequence[0].addr < last.addr)
+            {
+                continue;
+            }
+
+            rows.extend(sequence);
+        }
+
+        Ok(Self { rows, files })
+    }
+
+    /// Finds the source location of the row spanning `addr`.
+    pub fn find(&self, addr: Addr) -> Result<SourceLoc, Error> {
+        let row = self
+            .rows
+            .partition_point(|row| row.addr <= addr)
+            .checked_sub(1)
+            .map(|index| &self.rows[index])
+            .filter(|row| !row.end_sequence)
+            .ok_or(Error::AddrLineInfoMissing(addr))?;
+
+        Ok(SourceLoc {
+            file: self
+                .file(row.file)
+                .ok_or_else(|| Error::AddrFileInfoMissing(Addr::from(row.addr)))?
+                .to_path_buf(),
+            line: row.line,
+            col: row.col,
+        })
+    }
+
+    /// The rows spanning any address in `range`, along with the part of `range` each spans.
+    pub fn rows_in(&self, range: &Range<u64>) -> Vec<(Range<u64>, LineRow)> {
+        let start = self
+            .rows
+            .partition_point(|row| row.addr <= range.start)
+            .saturating_sub(1);
+
+        self.rows[start..]
+            .windows(2)
+            .take_while(|rows| rows[0].addr < range.end)
+            .filter(|rows| !rows[0].end_sequence)
+            .map(|rows| {
+                (
+                    rows[0].addr.max(range.start)..rows[1].addr.min(range.end),
+                    rows[0],
+                )
+            })
+            .filter(|(range, _)| range.start < range.end)
+            .collect()
+    }
+
+    /// The path of the file at `index` in the line program header's file table.
+    pub fn file(&self, index: u64) -> Option<&Path> {
+        self.files.get(index as usize)?.as_deref()
+    }
+}
@@ -0,0 +1,352 @@
+use crate::data::*;
+use itertools::Either;
+use object::Object;
+use pdb::{FallibleIterator, IdData, IdIndex, LineInfo, SymbolData, SymbolIndex};
+use std::{
+    collections::HashMap,
+    fs, mem,
+    path::{Path, PathBuf},
+};
+
+/// The debug info of a PE image in its PDB, indexed for symbolication.
+///
+/// A PE image names the PDB with its debug info, and identifies it by GUID and age, in the
+/// CodeView record of its debug directory. The functions of every module in the PDB, their
+/// inline sites and line numbers are read once, with their addresses relative to the image
+/// base, as the PDB can't be shared across threads.
+#[derive(Debug, Default)]
+pub struct PdbFile {
+    image_base: u64,
+    files: Vec<PathBuf>,
+    functions: Vec<PdbFunction>,
+}
+
+/// A procedure in a PDB, and the sites of the functions inlined into it.
+#[derive(Debug)]
+struct PdbFunction {
+    rva: u32,
+    len: u32,
+    name: String,
+    lines: Vec<PdbLine>,
+    inline_sites: Vec<PdbInlineSite>,
+}
+
+/// A site of a function inlined into a [`PdbFunction`], `depth` inline sites deep.
+#[derive(Debug)]
+struct PdbInlineSite {
+    depth: usize,
+    name: String,
+    lines: Vec<PdbLine>,
+}
+
+/// The source location of a range of instructions.
+#[derive(Clone, Copy, Debug)]
+struct PdbLine {
+    rva: u32,
+    len: u32,
+    file: usize,
+    line: u32,
+    col: u32,
+}
+
+impl PdbFile {
+    /// Finds the PDB of the PE image `obj` at `obj_path`.
+    ///
+    /// The PDB is looked up at the path in the CodeView record of the image, and then by file
+    /// name next to the image and in each of the `search_paths`. A PDB is only taken when its
+    /// GUID and age match the image's.
+    pub fn find(
+        obj: &object::File,
+        obj_path: &Path,
+        search_paths: &[PathBuf],
+    ) -> Result<Option<PathBuf>, Error> {
+        let Some(code_view) = obj.pdb_info()? else {
+            return Ok(None);
+        };
+
+        let pdb_path = String::from_utf8_lossy(code_view.path());
+        let pdb_name = pdb_path.rsplit(['/', '\\']).next().unwrap_or_default();
+        let obj_dir = obj_path.parent().unwrap_or(Path::new(""));
+
+        Ok([PathBuf::from(&*pdb_path), obj_dir.join(pdb_name)]
+            .into_iter()
+            .chain(search_paths.iter().map(|dir| dir.join(pdb_name)))
+            .find(|path| {
+                path.is_file()
+                    && is_matching_pdb(path, code_view.guid(), code_view.age()).unwrap_or_default()
+            }))
+    }
+
+    /// Loads the PDB at `path` of the PE image `obj`.
+    ///
+    /// The functions and lines of every module are read up front, however few addresses are
+    /// looked up in it.
+    pub fn load(obj: &object::File, path: &Path) -> Result<Self, Error> {
+        let mut pdb = pdb::PDB::open(fs::File::open(path)?)?;
+
+        if let Some(code_view) = obj.pdb_info()? {
+            if !pdb_matches(&mut pdb, code_view.guid(), code_view.age())? {
+                return Err(Error::PdbMismatch(path.to_path_buf()));
+            }
+        }
+
+        let address_map = pdb.address_map()?;
+        let string_table = pdb.string_table()?;
+
+        let id_information = pdb.id_information()?;
+        let mut id_finder = id_information.finder();
+        let mut ids = id_information.iter();
+        while ids.next()?.is_some() {
+            id_finder.update(&ids);
+        }
+
+        // Inlinees are named by their function ID, qualified by the ID of their scope, if any.
+        let id_name = |index: IdIndex| -> Option<String> {
+            match id_finder.find(index).ok()?.parse().ok()? {
+                IdData::Function(id) => Some(
+                    match id
+                        .scope
+                        .and_then(|scope| id_finder.find(scope).ok()?.parse().ok())
+                    {
+                        Some(IdData::String(scope)) => format!("{}::{}", scope.name, id.name),
+                        _ => id.name.to_string().into_owned(),
+                    },
+                ),
+                IdData::MemberFunction(id) => Some(id.name.to_string().into_owned()),
+                _ => None,
+            }
+        };
+
+        let mut pdb_file = Self {
+            image_base: obj.relative_address_base(),
+            ..Default::default()
+        };
+        let mut file_indexes = HashMap::<String, usize>::default();
+
+        let debug_information = pdb.debug_information()?;
+        let mut modules = debug_information.modules()?;
+        while let Some(module) = modules.next()? {
+            let Some(module_info) = pdb.module_info(&module)? else {
+                continue;
+            };
+
+            let line_program = module_info.line_program()?;
+            let inlinees = module_info
+                .inlinees()?
+                .map(|inlinee| Ok((inlinee.index(), inlinee)))
+                .collect::<HashMap<_, _>>()?;
+
+            let mut pdb_lines = |lines: Vec<LineInfo>| -> Result<Vec<PdbLine>, Error> {
+                let mut pdb_lines = Vec::with_capacity(lines.len());
+                for line in lines {
+                    let Some(rva) = line.offset.to_rva(&address_map) else {
+                        continue;
+                    };
+
+                    let file = line_program
+                        .get_file_info(line.file_index)?
+                        .name
+                        .to_string_lossy(&string_table)?
+                        .into_owned();
+
+                    let next_file = file_indexes.len();
+                    let file = *file_indexes.entry(file.clone()).or_insert_with(|| {
+                        pdb_file
+                            .files
+                            .push(PathBuf::from(file.replace('\\', "/")));
+                        next_file
+                    });
+
+                    pdb_lines.push(PdbLine {
+                        rva: rva.0,
+                        len: line.length.unwrap_or_default(),
+                        file,
+                        line: line.line_start,
+                        col: line.column_start.unwrap_or_default(),
+                    });
+                }
+
+                pdb_lines.sort_by_key(|line| line.rva);
+                Ok(pdb_lines)
+            };
+
+            let mut depths = HashMap::<SymbolIndex, usize>::default();
+            let mut parent_offset = None;
+
+            let mut symbols = module_info.symbols()?;
+            while let Some(symbol) = symbols.next()? {
+                match symbol.parse() {
+                    Ok(SymbolData::Procedure(procedure)) => {
+                        let Some(rva) = procedure.offset.to_rva(&address_map) else {
+                            parent_offset = None;
+                            continue;
+                        };
+
+                        depths.insert(symbol.index(), 0);
+                        parent_offset = Some(procedure.offset);
+
+                        let lines = pdb_lines(
+                            line_program
+                                .lines_for_symbol(procedure.offset)
+                                .collect()?,
+                        )?;
+
+                        pdb_file.functions.push(PdbFunction {
+                            rva: rva.0,
+                            len: procedure.len,
+                            name: procedure.name.to_string().into_owned(),
+                            lines,
+                            inline_sites: Vec::default(),
+                        });
+                    }
+
+                    Ok(SymbolData::InlineSite(site)) => {
+                        let (Some(offset), Some(inlinee)) =
+                            (parent_offset, inlinees.get(&site.inlinee))
+                        else {
+                            continue;
+                        };
+
+                        let depth = site
+                            .parent
+                            .and_then(|parent| depths.get(&parent))
+                            .map_or(1, |depth| depth + 1);
+
+                        depths.insert(symbol.index(), depth);
+
+                        let lines = pdb_lines(inlinee.lines(offset, &site).collect()?)?;
+                        if let Some(function) = pdb_file.functions.last_mut() {
+                            function.inline_sites.push(PdbInlineSite {
+                                depth,
+                                name: id_name(site.inlinee).unwrap_or_default(),
+                                lines,
+                            });
+                        }
+                    }
+
+                    _ => {}
+                }
+            }
+        }
+
+        pdb_file.functions.sort_by_key(|function| function.rva);
+        for function in &mut pdb_file.functions {
+            function
+                .inline_sites
+                .sort_by_key(|site| usize::MAX - site.depth);
+        }
+
+        Ok(pdb_file)
+    }
+
+    /// An estimate of the memory taken by the functions read from the PDB, in bytes, which holds
+    /// those of every module.
+    pub fn memory_size(&self) -> usize {
+        self.files
+            .iter()
+            .map(|file| mem::size_of::<PathBuf>() + file.as_os_str().len())
+            .chain(self.functions.iter().map(|function| {
+                mem::size_of::<PdbFunction>()
+                    + function.name.len()
+                    + mem::size_of_val(&function.lines[..])
+                    + function
+                        .inline_sites
+                        .iter()
+                        .map(|site| {
+                            mem::size_of::<PdbInlineSite>()
+                                + site.name.len()
+                                + mem::size_of_val(&site.lines[..])
+                        })
+                        .sum::<usize>()
+            }))
+            .sum()
+    }
+
+    /// Symbolicates `addr`, returning its symbols innermost first when `include_inlined`, or
+    /// the function spanning it at the innermost source location otherwise.
+    pub fn atos(&self, addr: Addr, include_inlined: bool) -> Result<Vec<Symbol>, Error> {
+        let rva = addr
+            .checked_sub(self.image_base)
+            .and_then(|rva| u32::try_from(rva).ok())
+            .ok_or(Error::AddrNotFound(addr))?;
+
+        let function = self
+            .functions
+            .partition_point(|function| function.rva <= rva)
+            .checked_sub(1)
+            .map(|index| &self.functions[index])
+            .filter(|function| rva - function.rva < function.len)
+            .ok_or(Error::AddrNotFound(addr))?;
+
+        let inlined = function
+            .inline_sites
+            .iter()
+            .filter_map(|site| Some((site.name.as_str(), find_line(&site.lines, rva)?)))
+            .collect::<Vec<_>>();
+
+        let loc = |line: Option<&PdbLine>| match line {
+            Some(line) => Either::Left(SourceLoc {
+                file: self.files[line.file].clone(),
+                line: line.line as u64,
+                col: line.col as u64,
+            }),
+            None => Either::Right(Offset::from((rva - function.rva) as u64)),
+        };
+
+        let function_line = find_line(&function.lines, rva);
+
+        if !include_inlined {
+            return Ok(vec![Symbol {
+                addr,
+                name: function.name.clone(),
+                mangled_name: None,
+                loc: loc(inlined
+                    .first()
+                    .map(|(_, line)| *line)
+                    .or(function_line)),
+                inlined: false,
+            }]);
+        }
+
+        Ok(inlined
+            .iter()
+            .map(|(name, line)| Symbol {
+                addr,
+                name: name.to_string(),
+                mangled_name: None,
+                loc: loc(Some(line)),
+                inlined: true,
+            })
+            .chain([Symbol {
+                addr,
+                name: function.name.clone(),
+                mangled_name: None,
+                loc: loc(function_line),
+                inlined: false,
+            }])
+            .collect())
+    }
+}
+
+/// Finds the line spanning `rva` in `lines`, sorted by address.
+fn find_line(lines: &[PdbLine], rva: u32) -> Option<&PdbLine> {
+    lines
+        .partition_point(|line| line.rva <= rva)
+        .checked_sub(1)
+        .map(|index| &lines[index])
+        .filter(|line| rva - line.rva < line.len.max(1))
+}
+
+fn is_matching_pdb(path: &Path, guid: [u8; 16], age: u32) -> Result<bool, Error> {
+    pdb_matches(&mut pdb::PDB::open(fs::File::open(path)?)?, guid, age)
+}
+
+/// Whether `pdb` has the GUID and age recorded in the CodeView record of a PE image.
+fn pdb_matches(pdb: &mut pdb::PDB<fs::File>, guid: [u8; 16], age: u32) -> Result<bool, Error> {
+    let age_matches = pdb
+        .debug_information()?
+        .age()
+        .map_or(true, |pdb_age| pdb_age == age);
+
+    Ok(pdb.pdb_information()?.guid.to_bytes_le() == guid && age_matches)
+}
@@ -0,0 +1,211 @@
+use crate::{data::*, symbolicator::DwarfExt, *};
+use gimli::{DwarfPackage, EndianSlice, RunTimeEndian, SectionId};
+use memmap2::Mmap;
+use object::{Object, ObjectSection};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The `.dwo` objects and `.dwp` packages holding the debug info of an object built with
+/// `-gsplit-dwarf`, mapped in memory for a [`Symbolicator`] to borrow from.
+///
+/// The main object then only has a skeleton unit per compilation unit, naming the `.dwo` its
+/// DIEs were split into with `DW_AT_dwo_name`, or `DW_AT_GNU_dwo_name` before DWARF 5, and
+/// identifying it with a DWO id which also keys its contribution to a `.dwp` package.
+#[derive(Debug, Default)]
+pub struct SplitDwarf {
+    dwos: Vec<(PathBuf, SplitFile)>,
+    dwps: Vec<SplitFile>,
+}
+
+/// A `.dwo` or `.dwp` mapped in memory, along with its compressed sections, decompressed when
+/// mapped as they can't be borrowed from the file.
+#[derive(Debug)]
+struct SplitFile {
+    mmap: Mmap,
+    decompressed: HashMap<String, Vec<u8>>,
+}
+
+impl SplitDwarf {
+    /// Maps the split DWARF referenced by the skeleton units of `dwarf`, the DWARF of the
+    /// object at `obj_path`.
+    ///
+    /// A `.dwp` package is looked up next to the object, as `<obj_path>.dwp`, and then in
+    /// each of the `search_paths`. Each `.dwo` is looked up at its path, relative to the
+    /// compilation directory of its unit, and then by file name in each of the `search_paths`.
+    /// Split DWARF that can't be found is skipped, leaving its units without debug info.
+    pub fn load(dwarf: &Dwarf, obj_path: &Path, search_paths: &[PathBuf]) -> Result<Self, Error> {
+        let mut split_dwarf = Self::default();
+        let mut dwo_names = Vec::default();
+
+        let mut headers = dwarf.units();
+        while let Some(header) = headers.next()? {
+            let unit = dwarf.unit(header)?;
+            if unit.dwo_id.is_none() {
+                continue;
+            }
+
+            if let Some(dwo_name) = dwarf.unit_dwo_name(&unit)? {
+                dwo_names.push(dwo_name);
+            }
+        }
+
+        if dwo_names.is_empty() {
+            return Ok(split_dwarf);
+        }
+
+        if let Some(obj_name) = obj_path.file_name() {
+            let mut dwp_name = obj_name.to_os_string();
+            dwp_name.push(".dwp");
+
+            let dwp_path = obj_path.with_file_name(&dwp_name);
+            if let Some(path) = [dwp_path]
+                .into_iter()
+                .chain(search_paths.iter().map(|dir| dir.join(&dwp_name)))
+                .find(|path| path.is_file())
+            {
+                split_dwarf.dwps.push(map(&path)?);
+            }
+        }
+
+        for dwo_name in dwo_names {
+            let Some(path) = [dwo_name.clone()]
+                .into_iter()
+                .chain(
+                    search_paths
+                        .iter()
+                        .filter_map(|dir| Some(dir.join(dwo_name.file_name()?))),
+                )
+                .find(|path| path.is_file())
+            else {
+                continue;
+            };
+
+            if split_dwarf.dwo(&dwo_name).is_none() {
+                split_dwarf.dwos.push((dwo_name, map(&path)?));
+            }
+        }
+
+        Ok(split_dwarf)
+    }
+
+    /// Loads the `.dwp` packages.
+    pub(crate) fn packages(
+        &self,
+    ) -> Result<Vec<DwarfPackage<EndianSlice<'_, RunTimeEndian>>>, Error> {
+        self.dwps
+            .iter()
+            .map(|file| {
+                let obj = object::File::parse(&*file.mmap)?;
+                let endian = endianness(&obj);
+
+                DwarfPackage::load(
+                    |section_id| -> Result<_, Error> {
+                        Ok(EndianSlice::new(
+                            index_section_data(file, &obj, section_id),
+                            endian,
+                        ))
+                    },
+                    EndianSlice::new(&[], endian),
+                )
+            })
+            .collect()
+    }
+
+    /// Loads the sections of the `.dwo` named `dwo_name` by its skeleton unit in `parent`.
+    pub(crate) fn load_dwo<'data>(
+        &'data self,
+        dwo_name: &Path,
+        parent: &Dwarf<'data>,
+    ) -> Result<Option<Dwarf<'data>>, Error> {
+        let Some(file) = self.dwo(dwo_name) else {
+            return Ok(None);
+        };
+
+        let obj = object::File::parse(&*file.mmap)?;
+        let endian = endianness(&obj);
+
+        let mut dwarf = gimli::Dwarf::load(|section_id| -> Result<_, Error> {
+            Ok(EndianSlice::new(
+                section_data(file, &obj, section_id),
+                endian,
+            ))
+        })?;
+
+        dwarf.make_dwo(parent);
+
+        Ok(Some(dwarf))
+    }
+
+    fn dwo(&self, dwo_name: &Path) -> Option<&SplitFile> {
+        self.dwos
+            .iter()
+            .find_map(|(name, file)| (name == dwo_name).then_some(file))
+    }
+}
+
+/// Maps the `.dwo` or `.dwp` at `path`, decompressing its compressed sections.
+fn map(path: &Path) -> Result<SplitFile, Error> {
+    let mmap = unsafe { Mmap::map(&fs::File::open(path)?) }?;
+
+    let mut decompressed = HashMap::default();
+    for section in object::File::parse(&*mmap)?.sections() {
+        if let Cow::Owned(data) = section.uncompressed_data()? {
+            decompressed.insert(section.name()?.to_owned(), data);
+        }
+    }
+
+    Ok(SplitFile { mmap, decompressed })
+}
+
+fn endianness(obj: &object::File) -> RunTimeEndian {
+    if obj.is_little_endian() {
+        RunTimeEndian::Little
+    } else {
+        RunTimeEndian::Big
+    }
+}
+
+/// The data of a section of a `.dwp` package, with unit indexes without units left empty.
+///
+/// GNU `dwp` writes a `.debug_tu_index` with no hash slots when there are no type units, which
+/// gimli rejects as an invalid index instead of reading it as an empty one.
+fn index_section_data<'data>(
+    file: &'data SplitFile,
+    obj: &object::File<'data>,
+    section_id: SectionId,
+) -> &'data [u8] {
+    let data = section_data(file, obj, section_id);
+    match section_id {
+        SectionId::DebugCuIndex | SectionId::DebugTuIndex
+            if data
+                .get(8..12)
+                .is_some_and(|unit_count| unit_count == [0; 4]) =>
+        {
+            &[]
+        }
+        _ => data,
+    }
+}
+
+/// The data of the `.dwo` variant of a section of `file`, parsed as `obj`.
+fn section_data<'data>(
+    file: &'data SplitFile,
+    obj: &object::File<'data>,
+    section_id: SectionId,
+) -> &'data [u8] {
+    let Some(name) = section_id.dwo_name() else {
+        return &[];
+    };
+
+    match file.decompressed.get(name) {
+        Some(data) => data,
+        None => obj
+            .section_by_name(name)
+            .and_then(|section| section.data().ok())
+            .unwrap_or_default(),
+    }
+}
@@ -0,0 +1,37 @@
+use regex::Regex;
+
+/// The name of the functions to look up the code of, or a regular expression matching it.
+///
+/// A function matches when its name, demangled or as found in the debug info or symbol table,
+/// is the name given, or is matched in full by it as a regular expression, so that names like
+/// `-[SKTGraphicView drawRect:]` are found as they are written.
+#[derive(Clone, Debug)]
+pub struct SymbolQuery {
+    name: String,
+    regex: Option<Regex>,
+}
+
+impl SymbolQuery {
+    /// A query for the functions named `pattern`, or matched in full by it as a regular
+    /// expression, when it's a valid one.
+    pub fn new(pattern: &str) -> Self {
+        Self {
+            name: pattern.to_owned(),
+            regex: Regex::new(&format!("^(?:{pattern})$")).ok(),
+        }
+    }
+
+    /// Whether a function named `name`, demangled from `mangled_name` if any, matches.
+    pub fn matches(&self, name: &str, mangled_name: Option<&str>) -> bool {
+        [Some(name), mangled_name]
+            .into_iter()
+            .flatten()
+            .any(|name| {
+                name == self.name
+                    || self
+                        .regex
+                        .as_ref()
+                        .is_some_and(|regex| regex.is_match(name))
+            })
+    }
+}
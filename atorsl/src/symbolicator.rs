@@ -1,187 +1,609 @@
-use crate::{data::*, *};
+use crate::{data::*, debug_map::DebugMap, line_table::LineTable, split_dwarf::SplitDwarf, *};
 use fallible_iterator::FallibleIterator;
 use gimli::{
-    ColumnType, DW_AT_abstract_origin, DW_AT_artificial, DW_AT_call_column, DW_AT_call_file,
-    DW_AT_call_line, DW_AT_high_pc, DW_AT_linkage_name, DW_AT_low_pc, DW_AT_name, DW_AT_ranges,
-    DW_AT_specification, DebugInfoOffset, LineRow, UnitSectionOffset,
+    DW_AT_abstract_origin, DW_AT_artificial, DW_AT_call_column, DW_AT_call_file, DW_AT_call_line,
+    DW_AT_linkage_name, DW_AT_name, DW_AT_specification, DebugInfoOffset, DwarfFileType,
+    DwarfPackage, EndianSlice, RunTimeEndian, UnitOffset, UnitSectionOffset,
 };
 use itertools::Either;
 use std::{
     borrow::Cow,
+    collections::{hash_map, HashMap},
+    ops::{Deref, Range},
     path::{Path, PathBuf},
+    sync::OnceLock,
 };
 
-pub fn atos_dwarf(dwarf: &Dwarf, addr: Addr, include_inlined: bool) -> Result<Vec<Symbol>, Error> {
-    let unit = dwarf.unit_from_addr(addr)?;
-    let mut entries = unit.entries();
-
-    let comp_dir = PathBuf::from(
-        &*unit
-            .comp_dir
-            .ok_or_else(|| Error::CompUnitDirMissing(addr))?
-            .to_string_lossy(),
-    );
-
-    let mut line_rows = unit
-        .line_program
-        .clone()
-        .ok_or_else(|| Error::CompUnitLineProgramMissing(addr))?
-        .rows();
-
-    let mut symbols = Vec::default();
-
-    let subprogram = loop {
-        let (_, entry) = entries
-            .next_dfs()?
-            .ok_or_else(|| Error::AddrNotFound(addr))?;
-
-        if matches!(
-            entry.tag(),
-            gimli::DW_TAG_subprogram if dwarf.entry_contains(entry, addr, &unit)
-        ) {
-            break entry;
+/// Symbolicates addresses against the debug information of a DWARF object.
+///
+/// Every compilation unit is indexed once, when the `Symbolicator` is built: `.debug_aranges`
+/// map addresses to units, falling back to the ranges of the units themselves for addresses
+/// the aranges don't cover, and each unit maps the address ranges of its subprograms to their
+/// DIEs, along with the inlined subroutines within them. A lookup is then a binary search on
+/// each index rather than a walk over every DIE of the unit, so build one per object and reuse
+/// it for every address symbolicated against that object.
+///
+/// Skeleton units, left in the object by `-gsplit-dwarf`, are indexed through the split unit
+/// holding their DIEs when built [`with_split_dwarf`](Self::with_split_dwarf), and addresses
+/// without debug info in the object are looked up in the object files of its
+/// [`DebugMap`], if given one [`with_debug_map`](Self::with_debug_map).
+pub struct Symbolicator<'data> {
+    dwarf: DwarfRef<'data>,
+    units: Vec<IndexedUnit<'data>>,
+    aranges: RangeIndex,
+
+    /// The `DW_AT_low_pc`/`DW_AT_high_pc`/`DW_AT_ranges` of each unit, or the ranges of its
+    /// subprograms when it has none, for toolchains omitting or truncating `.debug_aranges`.
+    unit_ranges: RangeIndex,
+
+    debug_map: Option<&'data DebugMap>,
+
+    /// A symbolicator for each object in the debug map, indexed on the first lookup that
+    /// falls in the object, or `None` when the object couldn't be found.
+    objects: Vec<OnceLock<Option<Symbolicator<'data>>>>,
+}
+
+/// The DWARF indexed by a [`Symbolicator`], either borrowed from the caller, or loaded from
+/// an object in a debug map.
+enum DwarfRef<'data> {
+    Borrowed(&'data Dwarf<'data>),
+    Owned(Box<Dwarf<'data>>),
+}
+
+/// A compilation unit, and the address ranges of the subprograms with code within it.
+struct IndexedUnit<'data> {
+    unit: Unit<'data>,
+
+    /// The split unit holding the DIEs of a skeleton unit, along with the DWARF of the `.dwo`
+    /// or `.dwp` it was loaded from. Line info stays in the skeleton unit.
+    split: Option<(Dwarf<'data>, Unit<'data>)>,
+
+    subprograms: Vec<Subprogram>,
+    ranges: RangeIndex,
+
+    /// The unit's line program, decoded on the first lookup that falls in the unit.
+    line_table: OnceLock<LineTable>,
+}
+
+/// A `DW_TAG_subprogram`, and the inlined subroutines within it.
+struct Subprogram {
+    offset: UnitOffset,
+    inlined: Vec<InlinedSubroutine>,
+}
+
+/// A `DW_TAG_inlined_subroutine`.
+///
+/// Kept in depth-first order within its subprogram, so the ones containing an address form
+/// the inlining chain from the outermost call to the innermost.
+struct InlinedSubroutine {
+    offset: UnitOffset,
+    ranges: Vec<Range<u64>>,
+
+    /// The number of inlined subroutines it's nested in within its subprogram.
+    depth: usize,
+}
+
+/// A subprogram with code, as indexed by a [`Symbolicator`], for its debug info to be written
+/// in another format.
+#[derive(Clone)]
+pub(crate) struct Function {
+    pub name: String,
+    pub mangled_name: Option<String>,
+    pub ranges: Vec<Range<u64>>,
+    pub inlined: Vec<InlinedFunction>,
+
+    /// The line rows within the ranges of the subprogram, along with the range each spans,
+    /// with their paths as [`Symbolicator::atos`] returns them.
+    pub lines: Vec<(Range<u64>, SourceLoc)>,
+
+    /// The compilation directory the relative paths of `lines` are relative to.
+    pub comp_dir: PathBuf,
+}
+
+/// A function inlined into a [`Function`], and the location it was called from.
+#[derive(Clone)]
+pub(crate) struct InlinedFunction {
+    pub name: String,
+    pub mangled_name: Option<String>,
+    pub depth: usize,
+    pub call_loc: SourceLoc,
+    pub ranges: Vec<Range<u64>>,
+}
+
+impl Function {
+    /// Moves every address of the function by `delta`, as when linked into a binary image.
+    fn shifted(mut self, delta: u64) -> Self {
+        let shift = |range: &mut Range<u64>| {
+            *range = range.start.wrapping_add(delta)..range.end.wrapping_add(delta);
+        };
+
+        self.ranges.iter_mut().for_each(shift);
+        self.lines
+            .iter_mut()
+            .for_each(|(range, _)| shift(range));
+
+        for inlined in &mut self.inlined {
+            inlined.ranges.iter_mut().for_each(shift);
         }
-    };
 
-    if include_inlined && subprogram.has_children() {
-        let mut parent = subprogram.clone();
-        let mut depth = 0;
+        self
+    }
+}
 
-        let leaf = loop {
-            let Some((step, child)) = entries.next_dfs()? else {
-                break parent;
-            };
+impl<'data> Symbolicator<'data> {
+    /// Indexes every compilation unit in `dwarf`.
+    pub fn new(dwarf: &'data Dwarf<'data>) -> Result<Self, Error> {
+        Self::index(DwarfRef::Borrowed(dwarf), None, None)
+    }
 
-            depth += step;
+    /// Indexes every compilation unit in `dwarf`, resolving skeleton units to their split
+    /// units in `split_dwarf`.
+    pub fn with_split_dwarf(
+        dwarf: &'data Dwarf<'data>,
+        split_dwarf: &'data SplitDwarf,
+    ) -> Result<Self, Error> {
+        Self::index(DwarfRef::Borrowed(dwarf), Some(split_dwarf), None)
+    }
+
+    /// Looks up addresses without debug info in the object files of `debug_map`, the debug
+    /// map of the object, each indexed on the first lookup that falls in it.
+    pub fn with_debug_map(self, debug_map: &'data DebugMap) -> Self {
+        Self {
+            debug_map: Some(debug_map),
+            objects: (0..debug_map.objects_len())
+                .map(|_| OnceLock::new())
+                .collect(),
+            ..self
+        }
+    }
+
+    /// Indexes the compilation units of `dwarf_ref`, or only the one `only_addr` falls in, as
+    /// found in `.debug_aranges` or else in the ranges of the units, for a single lookup. The
+    /// other units are still parsed, for references into their DIEs.
+    fn index(
+        dwarf_ref: DwarfRef<'data>,
+        split_dwarf: Option<&'data SplitDwarf>,
+        only_addr: Option<Addr>,
+    ) -> Result<Self, Error> {
+        let dwarf = &*dwarf_ref;
+        let packages = split_dwarf
+            .map(SplitDwarf::packages)
+            .transpose()?
+            .unwrap_or_default();
 
-            if depth <= 0 {
-                break parent;
+        let arange_unit = match only_addr {
+            Some(addr) => dwarf.arange_unit(addr)?,
+            None => None,
+        };
+
+        let is_indexed = |unit: &Unit| match (only_addr, arange_unit) {
+            (None, _) => true,
+            (Some(_), Some(offset)) => unit.header.offset() == offset,
+            (Some(addr), None) => dwarf
+                .unit_root_ranges(unit)
+                .iter()
+                .any(|range| range.contains(&addr)),
+        };
+
+        let mut units = Vec::default();
+
+        let mut headers = dwarf.units();
+        while let Some(header) = headers.next()? {
+            let unit = dwarf.unit(header)?;
+            if !is_indexed(&unit) {
+                units.push(IndexedUnit::unindexed(unit));
+                continue;
             }
 
-            if matches!(
-                child.tag(),
-                gimli::DW_TAG_inlined_subroutine if dwarf.entry_contains(child, addr, &unit)
-            ) {
-                symbols.insert(
-                    0,
-                    Symbol {
-                        addr,
-                        name: dwarf.entry_symbol(addr, &parent, &unit)?,
-                        loc: Either::Left(dwarf.entry_call_loc(
-                            child,
-                            &mut line_rows,
-                            &comp_dir,
-                            &unit,
-                        )?),
-                    },
-                );
+            let split = match split_dwarf {
+                Some(split_dwarf) => split_unit(dwarf, &unit, split_dwarf, &packages)?,
+                None => None,
+            };
+
+            units.push(IndexedUnit::new(dwarf, unit, split)?);
+        }
+
+        let mut aranges = Vec::default();
 
-                parent = child.clone();
+        let mut headers = dwarf.debug_aranges.headers();
+        while let Some(header) = headers.next()? {
+            let offset = UnitSectionOffset::from(header.debug_info_offset());
+            let Ok(unit) = units.binary_search_by_key(&offset, |unit| unit.unit.header.offset())
+            else {
+                continue;
+            };
+
+            let mut entries = header.entries();
+            while let Some(entry) = entries.next()? {
+                let end = entry
+                    .address()
+                    .checked_add(entry.length())
+                    .ok_or(gimli::Error::InvalidAddressRange)?;
+
+                aranges.push((entry.address()..end, unit));
             }
+        }
+
+        let unit_ranges = units
+            .iter()
+            .enumerate()
+            .flat_map(|(index, indexed_unit)| {
+                let ranges = dwarf.unit_root_ranges(&indexed_unit.unit);
+                if ranges.is_empty() {
+                    indexed_unit.ranges.ranges().cloned().collect()
+                } else {
+                    ranges
+                }
+                .into_iter()
+                .map(move |range| (range, index))
+            })
+            .collect();
+
+        Ok(Self {
+            dwarf: dwarf_ref,
+            units,
+            aranges: RangeIndex::new(aranges),
+            unit_ranges: RangeIndex::new(unit_ranges),
+            debug_map: None,
+            objects: Vec::default(),
+        })
+    }
+
+    /// Symbolicates `addr`, returning the innermost frame first when `include_inlined` is set.
+    pub fn atos(&self, addr: Addr, include_inlined: bool) -> Result<Vec<Symbol>, Error> {
+        let Some(indexed_unit) = self
+            .aranges
+            .find(addr)
+            .or_else(|| self.unit_ranges.find(addr))
+            .map(|unit| &self.units[unit])
+        else {
+            return self.atos_debug_map(addr, include_inlined);
         };
 
-        symbols.insert(
-            0,
-            Symbol {
-                addr,
-                name: dwarf.entry_symbol(addr, &leaf, &unit)?,
-                loc: Either::Left(dwarf.entry_debug_line(addr, &mut line_rows, &unit)?),
-            },
+        let unit = &indexed_unit.unit;
+        let (dies_dwarf, dies_unit) = indexed_unit.dies(&self.dwarf);
+
+        let comp_dir = PathBuf::from(
+            &*unit
+                .comp_dir
+                .ok_or(Error::CompUnitDirMissing(addr))?
+                .to_string_lossy(),
         );
-    } else {
+
+        let line_program = unit
+            .line_program
+            .as_ref()
+            .ok_or(Error::CompUnitLineProgramMissing(addr))?;
+
+        let subprogram = indexed_unit
+            .ranges
+            .find(addr)
+            .map(|subprogram| &indexed_unit.subprograms[subprogram])
+            .ok_or(Error::AddrNotFound(addr))?;
+
+        let line_table = indexed_unit.line_table(&self.dwarf, line_program)?;
+
+        let mut symbols = Vec::default();
+        let mut parent = dies_unit.entry(subprogram.offset)?;
+
+        if include_inlined {
+            for inlined in subprogram.inlined.iter().filter(|inlined| {
+                inlined
+                    .ranges
+                    .iter()
+                    .any(|range| range.contains(&addr))
+            }) {
+                let child = dies_unit.entry(inlined.offset)?;
+                let (name, mangled_name) =
+                    demangle(self.entry_symbol(addr, &parent, dies_dwarf, dies_unit)?);
+
+                // Every frame but the subprogram's, pushed first, was inlined into its caller.
+                symbols.push(Symbol {
+                    addr,
+                    name,
+                    mangled_name,
+                    loc: Either::Left(self.dwarf.entry_call_loc(
+                        &child,
+                        line_program.header(),
+                        &comp_dir,
+                        unit,
+                    )?),
+                    inlined: !symbols.is_empty(),
+                });
+
+                parent = child;
+            }
+        }
+
+        let (name, mangled_name) =
+            demangle(self.entry_symbol(addr, &parent, dies_dwarf, dies_unit)?);
+
         symbols.push(Symbol {
             addr,
-            name: dwarf.entry_symbol(addr, subprogram, &unit)?,
-            loc: Either::Left(dwarf.entry_debug_line(addr, &mut line_rows, &unit)?),
+            name,
+            mangled_name,
+            loc: Either::Left(line_table.find(addr)?),
+            inlined: !symbols.is_empty(),
         });
+
+        symbols.reverse();
+
+        Ok(symbols)
     }
 
-    Ok(symbols)
-}
+    /// Every subprogram with code in the DWARF, along with its inlined subroutines and line
+    /// rows, followed by the ones linked from the objects of the debug map, if any.
+    ///
+    /// Subprograms and inlined subroutines without a name are left out.
+    pub(crate) fn functions(&self) -> Result<Vec<Function>, Error> {
+        let mut functions = Vec::default();
 
-pub fn atos_map(
-    symbol_map: &object::SymbolMap<object::SymbolMapName>,
-    addr: Addr,
-) -> Result<Vec<Symbol>, Error> {
-    let mut symbols = Vec::default();
-    let mut symbol_map_iter = symbol_map.symbols().iter().peekable();
+        for indexed_unit in &self.units {
+            let unit = &indexed_unit.unit;
+            let (dies_dwarf, dies_unit) = indexed_unit.dies(&self.dwarf);
 
-    while let (Some(symbol), next_symbol) = (symbol_map_iter.next(), symbol_map_iter.peek()) {
-        if addr == symbol.address()
-            || (addr > symbol.address()
-                && (next_symbol.is_none()
-                    || next_symbol.is_some_and(|next| addr < next.address())))
-        {
-            let demangled_symbol_name = demangler::demangle(
-                symbol
-                    .name()
-                    .strip_prefix('_')
-                    .unwrap_or(symbol.name()),
+            let comp_dir = unit
+                .comp_dir
+                .map(|comp_dir| PathBuf::from(&*comp_dir.to_string_lossy()))
+                .unwrap_or_default();
+
+            let line_table = unit
+                .line_program
+                .as_ref()
+                .map(|line_program| indexed_unit.line_table(&self.dwarf, line_program))
+                .transpose()?;
+
+            let mut ranges = vec![Vec::default(); indexed_unit.subprograms.len()];
+            for (range, subprogram) in indexed_unit.ranges.entries() {
+                ranges[subprogram].push(range.clone());
+            }
+
+            for (subprogram, ranges) in indexed_unit.subprograms.iter().zip(ranges) {
+                let addr = Addr::from(ranges.first().map_or(0, |range| range.start));
+                let entry = dies_unit.entry(subprogram.offset)?;
+                let Ok(name) = self.entry_symbol(addr, &entry, dies_dwarf, dies_unit) else {
+                    continue;
+                };
+
+                let mut inlined = Vec::default();
+                for inlined_subroutine in &subprogram.inlined {
+                    let entry = dies_unit.entry(inlined_subroutine.offset)?;
+                    let Ok(inlined_name) = self.entry_symbol(addr, &entry, dies_dwarf, dies_unit)
+                    else {
+                        continue;
+                    };
+
+                    let call_loc = match &unit.line_program {
+                        Some(line_program) => self.dwarf.entry_call_loc(
+                            &entry,
+                            line_program.header(),
+                            &comp_dir,
+                            unit,
+                        )?,
+                        None => SourceLoc {
+                            file: comp_dir.join("<compiler-generated>"),
+                            line: 0,
+                            col: 0,
+                        },
+                    };
+
+                    let (name, mangled_name) = demangle(inlined_name);
+                    inlined.push(InlinedFunction {
+                        name,
+                        mangled_name,
+                        depth: inlined_subroutine.depth,
+                        call_loc,
+                        ranges: inlined_subroutine.ranges.clone(),
+                    });
+                }
+
+                let lines = line_table
+                    .map(|line_table| {
+                        ranges
+                            .iter()
+                            .flat_map(|range| line_table.rows_in(range))
+                            .filter_map(|(range, row)| {
+                                Some((
+                                    range,
+                                    SourceLoc {
+                                        file: line_table.file(row.file)?.to_path_buf(),
+                                        line: row.line,
+                                        col: row.col,
+                                    },
+                                ))
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                let (name, mangled_name) = demangle(name);
+                functions.push(Function {
+                    name,
+                    mangled_name,
+                    ranges,
+                    inlined,
+                    lines,
+                    comp_dir: comp_dir.clone(),
+                });
+            }
+        }
+
+        let Some(debug_map) = self.debug_map else {
+            return Ok(functions);
+        };
+
+        let mut objects = HashMap::<usize, Vec<Function>>::default();
+        for (addr, size, object, object_addr) in debug_map.symbols()? {
+            let object_functions = match objects.entry(object) {
+                hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                hash_map::Entry::Vacant(entry) => entry.insert(match self.object(object)? {
+                    Some(symbolicator) => symbolicator.functions()?,
+                    None => Vec::default(),
+                }),
+            };
+
+            // Functions of unknown size are only taken when they start at the symbol.
+            let object_range = object_addr..object_addr + size.max(1);
+
+            functions.extend(
+                object_functions
+                    .iter()
+                    .filter(|function| {
+                        function
+                            .ranges
+                            .first()
+                            .is_some_and(|range| object_range.contains(&range.start))
+                    })
+                    .map(|function| {
+                        function
+                            .clone()
+                            .shifted(addr.wrapping_sub(object_addr))
+                    }),
             );
+        }
 
-            symbols.push(Symbol {
-                addr,
-                name: demangled_symbol_name.to_string(),
-                loc: Either::Right(Offset::from(*addr - symbol.address())),
-            });
+        Ok(functions)
+    }
+
+    /// The subprograms matching `query`, along with every inlined copy of the ones that do,
+    /// wherever they were inlined into.
+    pub fn lookup_symbol(&self, query: &SymbolQuery) -> Result<Vec<SymbolRanges>, Error> {
+        let addr_ranges = |ranges: &[Range<u64>]| {
+            ranges
+                .iter()
+                .map(|range| Addr::from(range.start)..Addr::from(range.end))
+                .collect()
+        };
+
+        let mut found = Vec::default();
+        for function in self.functions()? {
+            if query.matches(&function.name, function.mangled_name.as_deref()) {
+                found.push(SymbolRanges {
+                    name: function.name.clone(),
+                    mangled_name: function.mangled_name.clone(),
+                    ranges: addr_ranges(&function.ranges),
+                    inlined_into: None,
+                    source: Source::Dwarf,
+                });
+            }
+
+            // The names of the inlined subroutines enclosing the current one, by depth.
+            let mut callers = Vec::<&str>::default();
+            for inlined in &function.inlined {
+                callers.truncate(inlined.depth);
+                let caller = callers.last().copied().unwrap_or(&function.name);
+
+                if query.matches(&inlined.name, inlined.mangled_name.as_deref()) {
+                    found.push(SymbolRanges {
+                        name: inlined.name.clone(),
+                        mangled_name: inlined.mangled_name.clone(),
+                        ranges: addr_ranges(&inlined.ranges),
+                        inlined_into: Some((caller.to_owned(), inlined.call_loc.clone())),
+                        source: Source::Dwarf,
+                    });
+                }
+
+                callers.push(&inlined.name);
+            }
         }
+
+        Ok(found)
     }
 
-    Ok(symbols)
-}
+    /// The ranges of code the line rows of every subprogram attribute to `line` of `file`,
+    /// matching the last components of the path of each row, within its compilation directory,
+    /// along with the frames of each.
+    ///
+    /// Consecutive rows of the line are merged into a single range. A range whose frames can't
+    /// be found is kept, with the error finding them.
+    pub fn lookup_line(&self, file: &Path, line: u64) -> Result<Vec<LineRange>, Error> {
+        let mut ranges = Vec::<(Range<u64>, SourceLoc)>::default();
 
-trait DwarfExt {
-    fn entry_symbol<'a>(
-        &'a self,
-        addr: Addr,
-        entry: &'a Entry,
-        unit: &'a Unit,
-    ) -> Result<String, Error>;
+        for function in self.functions()? {
+            for (range, loc) in function.lines {
+                if loc.line != line || !function.comp_dir.join(&loc.file).ends_with(file) {
+                    continue;
+                }
 
-    fn entry_call_loc(
-        &self,
-        entry: &Entry,
-        line_rows: &mut IncompleteLineProgramRows,
-        path: &Path,
-        unit: &Unit,
-    ) -> Result<SourceLoc, Error>;
+                match ranges.last_mut() {
+                    Some((last, last_loc))
+                        if last.end == range.start && last_loc.file == loc.file =>
+                    {
+                        last.end = range.end;
+                    }
+                    _ => ranges.push((range, loc)),
+                }
+            }
+        }
 
-    fn entry_debug_line(
-        &self,
-        addr: Addr,
-        line_rows: &mut IncompleteLineProgramRows,
-        unit: &Unit,
-    ) -> Result<SourceLoc, Error>;
+        Ok(ranges
+            .into_iter()
+            .map(|(range, loc)| LineRange {
+                symbols: self.atos(Addr::from(range.start), true),
+                range: Addr::from(range.start)..Addr::from(range.end),
+                loc,
+            })
+            .collect())
+    }
 
-    fn entry_contains(&self, entry: &Entry, addr: Addr, unit: &Unit) -> bool;
-    fn entry_pc_contains(&self, entry: &Entry, addr: Addr) -> Option<bool>;
-    fn entry_ranges_contain(&self, entry: &Entry, addr: Addr, unit: &Unit) -> Option<bool>;
+    /// Symbolicates `addr` against the object of the debug map it was linked from.
+    fn atos_debug_map(&self, addr: Addr, include_inlined: bool) -> Result<Vec<Symbol>, Error> {
+        let Some((object, object_addr)) = self
+            .debug_map
+            .map(|debug_map| debug_map.find(addr))
+            .transpose()?
+            .flatten()
+        else {
+            return Err(Error::AddrDebugInfoOffsetMissing(addr));
+        };
 
-    fn line_row_file(
-        &self,
-        row: &LineRow,
-        header: &LineProgramHeader,
-        unit: &Unit,
-    ) -> Result<PathBuf, Error>;
+        let Some(symbolicator) = self.object(object)? else {
+            return Err(Error::AddrDebugInfoOffsetMissing(addr));
+        };
 
-    fn attr_lossy_string<'a>(
-        &'a self,
-        unit: &Unit<'a>,
-        attr: AttrValue<'a>,
-    ) -> Result<Cow<str>, gimli::Error>;
+        // Report the address symbolicated rather than the one it maps to in the object, for
+        // callers falling back to the symbol table on errors.
+        let mut symbols = symbolicator
+            .atos(object_addr, include_inlined)
+            .map_err(|err| match err {
+                Error::AddrNotFound(_) => Error::AddrNotFound(addr),
+                Error::AddrDebugInfoOffsetMissing(_) => Error::AddrDebugInfoOffsetMissing(addr),
+                Error::AddrLineInfoMissing(_) => Error::AddrLineInfoMissing(addr),
+                err => err,
+            })?;
 
-    fn unit_from_offset(&self, addr: Addr, offset: DebugInfoOffset) -> Result<Unit, Error>;
-    fn unit_from_addr(&self, addr: Addr) -> Result<Unit, Error>;
+        symbols
+            .iter_mut()
+            .for_each(|symbol| symbol.addr = addr);
 
-    fn debug_info_offset(&self, addr: Addr) -> Result<DebugInfoOffset, Error>;
-}
+        Ok(symbols)
+    }
 
-impl DwarfExt for Dwarf<'_> {
-    fn entry_symbol<'a>(
-        &'a self,
+    /// The symbolicator of the debug map object at `index`, indexing it on first use.
+    fn object(&self, index: usize) -> Result<Option<&Symbolicator<'data>>, Error> {
+        let (Some(debug_map), Some(object)) = (self.debug_map, self.objects.get(index)) else {
+            return Ok(None);
+        };
+
+        if let Some(symbolicator) = object.get() {
+            return Ok(symbolicator.as_ref());
+        }
+
+        let symbolicator = debug_map
+            .object_dwarf(index)?
+            .map(|dwarf| Self::index(DwarfRef::Owned(Box::new(dwarf)), None, None))
+            .transpose()?;
+
+        Ok(object.get_or_init(|| symbolicator).as_ref())
+    }
+
+    /// The symbol name of `entry`, as found in the DWARF, before demangling.
+    fn entry_symbol(
+        &self,
         addr: Addr,
-        entry: &'a Entry,
-        unit: &'a Unit,
+        entry: &Entry,
+        dwarf: &Dwarf<'data>,
+        unit: &Unit<'data>,
     ) -> Result<String, Error> {
         let attr_value = [
             DW_AT_linkage_name,
@@ -194,72 +616,364 @@ impl DwarfExt for Dwarf<'_> {
         .ok_or(Error::AddrSymbolMissing(addr))?;
 
         let symbol = match attr_value {
-            AttrValue::UnitRef(offset) => self.entry_symbol(addr, &unit.entry(offset)?, unit)?,
+            AttrValue::UnitRef(offset) => {
+                self.entry_symbol(addr, &unit.entry(offset)?, dwarf, unit)?
+            }
+            AttrValue::DebugInfoRef(_) if dwarf.file_type == DwarfFileType::Dwo => {
+                Err(Error::AddrDebugInfoRefOffsetNofFound(addr))?
+            }
             AttrValue::DebugInfoRef(offset) => {
                 let new_unit = self.unit_from_offset(addr, offset)?;
                 let new_entry = new_unit.entry(
                     UnitSectionOffset::from(offset)
-                        .to_unit_offset(&new_unit)
+                        .to_unit_offset(new_unit)
                         .ok_or(Error::AddrDebugInfoRefOffsetOutOfBounds(addr))?,
                 )?;
 
-                self.entry_symbol(addr, &new_entry, &new_unit)?
+                self.entry_symbol(addr, &new_entry, &self.dwarf, new_unit)?
             }
-            attr => demangler::demangle(&self.attr_lossy_string(unit, attr)?).into_owned(),
+            attr => dwarf.attr_lossy_string(unit, attr)?.into_owned(),
         };
 
         Ok(symbol)
     }
 
-    fn entry_debug_line(
+    fn unit_from_offset(
         &self,
         addr: Addr,
-        line_rows: &mut IncompleteLineProgramRows,
-        unit: &Unit,
-    ) -> Result<SourceLoc, Error> {
-        let mut source_locs = Vec::default();
-
-        let mut prev_row: Option<LineRow> = None;
-        while let Some((header, line_row)) = line_rows.next_row()? {
-            if let Some(prev_row_value) = prev_row {
-                let previous_match = prev_row_value.address() <= addr && addr < line_row.address();
-
-                if previous_match {
-                    source_locs.push(SourceLoc {
-                        file: self.line_row_file(&prev_row_value, header, unit)?,
-                        line: prev_row_value
-                            .line()
-                            .map(|line| line.get())
-                            .unwrap_or_default(),
-                        col: match prev_row_value.column() {
-                            ColumnType::LeftEdge => 0,
-                            ColumnType::Column(col) => col.get(),
-                        },
+        offset: DebugInfoOffset,
+    ) -> Result<&Unit<'data>, Error> {
+        let unit_offset = UnitSectionOffset::from(offset);
+
+        self.units
+            .partition_point(|unit| unit.unit.header.offset() <= unit_offset)
+            .checked_sub(1)
+            .map(|index| &self.units[index].unit)
+            .ok_or(Error::AddrDebugInfoRefOffsetNofFound(addr))
+    }
+}
+
+impl<'data> IndexedUnit<'data> {
+    fn new(
+        dwarf: &Dwarf<'data>,
+        unit: Unit<'data>,
+        split: Option<(Dwarf<'data>, Unit<'data>)>,
+    ) -> Result<Self, Error> {
+        let (dwarf, dies_unit) = match &split {
+            Some((split_dwarf, split_unit)) => (split_dwarf, split_unit),
+            None => (dwarf, &unit),
+        };
+
+        let mut subprograms = Vec::<Subprogram>::default();
+        let mut ranges = Vec::default();
+
+        // The subprograms enclosing the current entry, along with the depth they're at, and
+        // the depth of the inlined subroutines enclosing it.
+        let mut enclosing = Vec::<(isize, usize)>::default();
+        let mut enclosing_inlined = Vec::<isize>::default();
+        let mut depth = 0;
+
+        let mut entries = dies_unit.entries();
+        while let Some((step, entry)) = entries.next_dfs()? {
+            depth += step;
+
+            while enclosing.last().is_some_and(|(at, _)| depth <= *at) {
+                enclosing.pop();
+            }
+
+            while enclosing_inlined.last().is_some_and(|at| depth <= *at) {
+                enclosing_inlined.pop();
+            }
+
+            match entry.tag() {
+                gimli::DW_TAG_subprogram => {
+                    let entry_ranges = dwarf.entry_ranges(entry, dies_unit);
+                    if entry_ranges.is_empty() {
+                        continue;
+                    }
+
+                    let subprogram = subprograms.len();
+                    ranges.extend(
+                        entry_ranges
+                            .into_iter()
+                            .map(|range| (range, subprogram)),
+                    );
+                    subprograms.push(Subprogram {
+                        offset: entry.offset(),
+                        inlined: Vec::default(),
                     });
+
+                    enclosing.push((depth, subprogram));
                 }
-            }
 
-            prev_row = Some(line_row.to_owned());
+                gimli::DW_TAG_inlined_subroutine => {
+                    let Some(&(_, subprogram)) = enclosing.last() else {
+                        continue;
+                    };
+
+                    let entry_ranges = dwarf.entry_ranges(entry, dies_unit);
+                    if !entry_ranges.is_empty() {
+                        subprograms[subprogram]
+                            .inlined
+                            .push(InlinedSubroutine {
+                                offset: entry.offset(),
+                                ranges: entry_ranges,
+                                depth: enclosing_inlined.len(),
+                            });
 
-            if line_row.end_sequence() {
-                prev_row = None;
+                        enclosing_inlined.push(depth);
+                    }
+                }
+
+                _ => {}
             }
         }
 
-        source_locs
-            .pop()
-            .ok_or(Error::AddrLineInfoMissing(addr))
+        Ok(Self {
+            unit,
+            split,
+            subprograms,
+            ranges: RangeIndex::new(ranges),
+            line_table: OnceLock::default(),
+        })
+    }
+
+    /// A unit left out of the index, only parsed for references into its DIEs.
+    fn unindexed(unit: Unit<'data>) -> Self {
+        Self {
+            unit,
+            split: None,
+            subprograms: Vec::default(),
+            ranges: RangeIndex::new(Vec::default()),
+            line_table: OnceLock::default(),
+        }
+    }
+
+    /// The DWARF and unit holding the DIEs of this unit, which differ from the ones it was
+    /// indexed from for a skeleton unit.
+    fn dies<'a>(&'a self, dwarf: &'a Dwarf<'data>) -> (&'a Dwarf<'data>, &'a Unit<'data>) {
+        match &self.split {
+            Some((split_dwarf, split_unit)) => (split_dwarf, split_unit),
+            None => (dwarf, &self.unit),
+        }
+    }
+
+    fn line_table(
+        &self,
+        dwarf: &Dwarf<'data>,
+        line_program: &IncompleteLineProgram<'data>,
+    ) -> Result<&LineTable, Error> {
+        if let Some(line_table) = self.line_table.get() {
+            return Ok(line_table);
+        }
+
+        let line_table = LineTable::new(dwarf, &self.unit, line_program.clone())?;
+
+        Ok(self.line_table.get_or_init(|| line_table))
     }
+}
+
+impl<'data> Deref for DwarfRef<'data> {
+    type Target = Dwarf<'data>;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            DwarfRef::Borrowed(dwarf) => dwarf,
+            DwarfRef::Owned(dwarf) => dwarf,
+        }
+    }
+}
+
+/// Loads the split unit of a skeleton unit, from the `.dwp` packages or its `.dwo`.
+fn split_unit<'data>(
+    dwarf: &Dwarf<'data>,
+    skeleton: &Unit<'data>,
+    split_dwarf: &'data SplitDwarf,
+    packages: &[DwarfPackage<EndianSlice<'data, RunTimeEndian>>],
+) -> Result<Option<(Dwarf<'data>, Unit<'data>)>, Error> {
+    let Some(dwo_id) = skeleton.dwo_id else {
+        return Ok(None);
+    };
 
+    let package_dwarf = packages
+        .iter()
+        .find_map(|package| package.find_cu(dwo_id, dwarf).transpose())
+        .transpose()?;
+
+    let split_dwarf = match package_dwarf {
+        Some(package_dwarf) => package_dwarf,
+        None => match dwarf.unit_dwo_name(skeleton)? {
+            Some(dwo_name) => match split_dwarf.load_dwo(&dwo_name, dwarf)? {
+                Some(dwo_dwarf) => dwo_dwarf,
+                None => return Ok(None),
+            },
+            None => return Ok(None),
+        },
+    };
+
+    let mut headers = split_dwarf.units();
+    while let Some(header) = headers.next()? {
+        let mut split_unit = split_dwarf.unit(header)?;
+        if split_unit.dwo_id == Some(dwo_id) {
+            split_unit.copy_relocated_attributes(skeleton);
+            return Ok(Some((split_dwarf, split_unit)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Address ranges sorted by their start, each mapped to the index of what it covers.
+struct RangeIndex(Vec<IndexedRange>);
+
+struct IndexedRange {
+    range: Range<u64>,
+
+    /// The highest end of this and every preceding range, bounds the backwards scan of a
+    /// lookup when ranges overlap.
+    max_end: u64,
+
+    index: usize,
+}
+
+impl RangeIndex {
+    fn new(mut ranges: Vec<(Range<u64>, usize)>) -> Self {
+        ranges.sort_unstable_by_key(|(range, index)| (range.start, *index));
+
+        let mut max_end = 0;
+        Self(
+            ranges
+                .into_iter()
+                .map(|(range, index)| {
+                    max_end = max_end.max(range.end);
+                    IndexedRange { range, max_end, index }
+                })
+                .collect(),
+        )
+    }
+
+    /// Finds the lowest index whose range contains `addr`, which, as ranges are indexed in the
+    /// order they're found, is the one a linear search through the debug info would find.
+    fn find(&self, addr: Addr) -> Option<usize> {
+        let upper = self
+            .0
+            .partition_point(|indexed| indexed.range.start <= addr);
+
+        self.0[..upper]
+            .iter()
+            .rev()
+            .take_while(|indexed| addr < indexed.max_end)
+            .filter(|indexed| indexed.range.contains(&addr))
+            .map(|indexed| indexed.index)
+            .min()
+    }
+
+    fn ranges(&self) -> impl Iterator<Item = &Range<u64>> {
+        self.0.iter().map(|indexed| &indexed.range)
+    }
+
+    fn entries(&self) -> impl Iterator<Item = (&Range<u64>, usize)> {
+        self.0
+            .iter()
+            .map(|indexed| (&indexed.range, indexed.index))
+    }
+}
+
+/// Symbolicates a single address, indexing only the compilation unit it falls in.
+///
+/// Every call still parses the header of each unit, so build a [`Symbolicator`] to symbolicate
+/// many addresses against the same DWARF.
+pub fn atos_dwarf(dwarf: &Dwarf, addr: Addr, include_inlined: bool) -> Result<Vec<Symbol>, Error> {
+    Symbolicator::index(DwarfRef::Borrowed(dwarf), None, Some(addr))?.atos(addr, include_inlined)
+}
+
+pub fn atos_map(
+    symbol_map: &object::SymbolMap<object::SymbolMapName>,
+    addr: Addr,
+) -> Result<Vec<Symbol>, Error> {
+    atos_symbols(
+        symbol_map
+            .symbols()
+            .iter()
+            .map(|symbol| (symbol.address(), symbol.name())),
+        addr,
+    )
+}
+
+/// Symbolicates `addr` to the symbols spanning it, given their address and name in the order
+/// of their address, as found in a symbol map.
+pub(crate) fn atos_symbols<'a>(
+    symbols: impl Iterator<Item = (u64, &'a str)>,
+    addr: Addr,
+) -> Result<Vec<Symbol>, Error> {
+    let mut symbols_found = Vec::default();
+    let mut symbols = symbols.peekable();
+
+    while let (Some((symbol_addr, symbol_name)), next_symbol) = (symbols.next(), symbols.peek()) {
+        if addr == symbol_addr
+            || (addr > symbol_addr
+                && (next_symbol.is_none()
+                    || next_symbol.is_some_and(|(next_addr, _)| addr < *next_addr)))
+        {
+            let (name, mangled_name) = demangle(
+                symbol_name
+                    .strip_prefix('_')
+                    .unwrap_or(symbol_name)
+                    .to_owned(),
+            );
+
+            symbols_found.push(Symbol {
+                addr,
+                name,
+                mangled_name,
+                loc: Either::Right(Offset::from(*addr - symbol_addr)),
+                inlined: false,
+            });
+        }
+    }
+
+    Ok(symbols_found)
+}
+
+/// Demangles `symbol`, returning it as the mangled name if it was demangled.
+pub(crate) fn demangle(symbol: String) -> (String, Option<String>) {
+    match demangler::demangle(&symbol) {
+        Cow::Owned(name) if name != symbol => (name, Some(symbol)),
+        _ => (symbol, None),
+    }
+}
+
+pub(crate) trait DwarfExt {
     fn entry_call_loc(
         &self,
         entry: &Entry,
-        line_rows: &mut IncompleteLineProgramRows,
+        header: &LineProgramHeader,
+        path: &Path,
+        unit: &Unit,
+    ) -> Result<SourceLoc, Error>;
+
+    fn entry_ranges(&self, entry: &Entry, unit: &Unit) -> Vec<Range<u64>>;
+    fn unit_root_ranges(&self, unit: &Unit) -> Vec<Range<u64>>;
+    fn arange_unit(&self, addr: Addr) -> Result<Option<UnitSectionOffset>, Error>;
+    fn unit_dwo_name(&self, unit: &Unit) -> Result<Option<PathBuf>, Error>;
+
+    fn attr_lossy_string<'a>(
+        &'a self,
+        unit: &Unit<'a>,
+        attr: AttrValue<'a>,
+    ) -> Result<Cow<'a, str>, gimli::Error>;
+}
+
+impl DwarfExt for Dwarf<'_> {
+    fn entry_call_loc(
+        &self,
+        entry: &Entry,
+        header: &LineProgramHeader,
         path: &Path,
         unit: &Unit,
     ) -> Result<SourceLoc, Error> {
         let Some(file) = (match entry.attr_value(DW_AT_call_file)? {
-            Some(AttrValue::FileIndex(offset)) => line_rows.header().file(offset),
+            Some(AttrValue::FileIndex(offset)) => header.file(offset),
             _ => None,
         }) else {
             return Ok(SourceLoc {
@@ -273,7 +987,7 @@ impl DwarfExt for Dwarf<'_> {
 
         Ok(SourceLoc {
             file: file
-                .directory(line_rows.header())
+                .directory(header)
                 .and_then(|dir| Some(PathBuf::from(&*self.attr_lossy_string(unit, dir).ok()?)))
                 .unwrap_or(path.to_path_buf())
                 .join(&*self.attr_lossy_string(unit, file.path_name())?),
@@ -298,107 +1012,61 @@ impl DwarfExt for Dwarf<'_> {
         })
     }
 
-    fn entry_contains(&self, entry: &Entry, addr: Addr, unit: &Unit) -> bool {
-        self.entry_pc_contains(entry, addr)
-            .or_else(|| self.entry_ranges_contain(entry, addr, unit))
-            .unwrap_or(false)
+    fn entry_ranges(&self, entry: &Entry, unit: &Unit) -> Vec<Range<u64>> {
+        self.die_ranges(unit, entry)
+            .and_then(|ranges| {
+                ranges
+                    .filter(|range| Ok(range.begin < range.end))
+                    .map(|range| Ok(range.begin..range.end))
+                    .collect()
+            })
+            .unwrap_or_default()
     }
 
-    fn entry_pc_contains(&self, entry: &Entry, addr: Addr) -> Option<bool> {
-        let low = match entry.attr_value(DW_AT_low_pc).ok()?? {
-            AttrValue::Addr(addr) => addr,
-            _ => None?,
-        };
+    fn unit_root_ranges(&self, unit: &Unit) -> Vec<Range<u64>> {
+        self.unit_ranges(unit)
+            .and_then(|ranges| {
+                ranges
+                    .filter(|range| Ok(range.begin < range.end))
+                    .map(|range| Ok(range.begin..range.end))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
 
-        let high = match entry.attr_value(DW_AT_high_pc).ok()?? {
-            AttrValue::Addr(addr) => addr,
-            AttrValue::Udata(len) => low + len,
-            _ => None?,
-        };
+    /// The offset of the unit `.debug_aranges` maps `addr` to.
+    fn arange_unit(&self, addr: Addr) -> Result<Option<UnitSectionOffset>, Error> {
+        let mut headers = self.debug_aranges.headers();
+        while let Some(header) = headers.next()? {
+            let mut entries = header.entries();
+            while let Some(entry) = entries.next()? {
+                if entry.address() <= *addr && *addr - entry.address() < entry.length() {
+                    return Ok(Some(UnitSectionOffset::from(header.debug_info_offset())));
+                }
+            }
+        }
 
-        Some((low..high).contains(&addr))
+        Ok(None)
     }
 
-    fn entry_ranges_contain(&self, entry: &Entry, addr: Addr, unit: &Unit) -> Option<bool> {
-        let AttrValue::RangeListsRef(offset) = entry.attr_value(DW_AT_ranges).ok()?? else {
-            None?
+    fn unit_dwo_name(&self, unit: &Unit) -> Result<Option<PathBuf>, Error> {
+        let Some(dwo_name) = unit.dwo_name()? else {
+            return Ok(None);
         };
 
-        self.ranges(unit, self.ranges_offset_from_raw(unit, offset))
-            .and_then(|mut rs| rs.any(|r| Ok((r.begin..r.end).contains(&addr))))
-            .ok()
-    }
+        let dwo_name = PathBuf::from(&*self.attr_lossy_string(unit, dwo_name)?);
 
-    fn line_row_file(
-        &self,
-        row: &LineRow,
-        header: &LineProgramHeader,
-        unit: &Unit,
-    ) -> Result<PathBuf, Error> {
-        row.file(header)
-            .ok_or_else(|| Error::AddrFileInfoMissing(Addr::from(row.address())))
-            .and_then(|file| {
-                Ok(match file.directory(header) {
-                    Some(dir) if file.directory_index() != 0 => {
-                        PathBuf::from(&*self.attr_lossy_string(unit, dir)?)
-                    }
-                    _ => PathBuf::default(),
-                }
-                .join(&*self.attr_lossy_string(unit, file.path_name())?))
-            })
+        Ok(Some(match unit.comp_dir {
+            Some(comp_dir) => PathBuf::from(&*comp_dir.to_string_lossy()).join(dwo_name),
+            None => dwo_name,
+        }))
     }
 
     fn attr_lossy_string<'input>(
         &'input self,
         unit: &Unit<'input>,
         attr: AttrValue<'input>,
-    ) -> Result<Cow<'_, str>, gimli::Error> {
+    ) -> Result<Cow<'input, str>, gimli::Error> {
         Ok(self.attr_string(unit, attr)?.to_string_lossy())
     }
-
-    fn unit_from_offset(&self, addr: Addr, offset: DebugInfoOffset) -> Result<Unit, Error> {
-        let unit_offset = UnitSectionOffset::from(offset);
-        let mut headers = self.units().peekable();
-        let header = loop {
-            match (headers.next()?, headers.peek()?) {
-                (Some(header), Some(next_header))
-                    if (header.offset()..next_header.offset()).contains(&unit_offset) =>
-                {
-                    break header
-                }
-                (Some(header), None) if unit_offset > header.offset() => break header,
-                (None, _) => Err(Error::AddrDebugInfoRefOffsetNofFound(addr))?,
-                (_, _) => continue,
-            };
-        };
-        Ok(self.unit(header)?)
-    }
-
-    fn unit_from_addr(&self, addr: Addr) -> Result<Unit, Error> {
-        let offset = self.debug_info_offset(addr)?;
-        let header = self.debug_info.header_from_offset(offset)?;
-        Ok(self.unit(header)?)
-    }
-
-    fn debug_info_offset(&self, addr: Addr) -> Result<DebugInfoOffset, Error> {
-        let contains = |addr| {
-            move |arange: gimli::ArangeEntry| {
-                arange
-                    .address()
-                    .checked_add(arange.length())
-                    .map(|address_end| (arange.address()..address_end).contains(addr))
-                    .ok_or(gimli::Error::InvalidAddressRange)
-            }
-        };
-
-        self.debug_aranges
-            .headers()
-            .find_map(|header| {
-                Ok(header
-                    .entries()
-                    .any(contains(&addr))?
-                    .then(|| header.debug_info_offset()))
-            })?
-            .ok_or(Error::AddrDebugInfoOffsetMissing(addr))
-    }
 }
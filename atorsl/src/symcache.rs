@@ -0,0 +1,459 @@
+use crate::{
+    data::*,
+    debug_file,
+    ext::object::Architecture as _,
+    symbolicator::{atos_symbols, Symbolicator},
+};
+use itertools::Either;
+use object::Object;
+use std::{collections::HashMap, io, ops::Range, path::PathBuf, str};
+
+/// The magic the file of a [`SymCache`] starts with.
+const MAGIC: &[u8; 8] = b"ATORSLSC";
+
+/// The version of the format of a [`SymCache`], bumped on every change to it.
+pub const SYMCACHE_VERSION: u32 = 1;
+
+const HEADER_LEN: usize = 40;
+const FUNCTION_LEN: usize = 40;
+const INLINED_LEN: usize = 48;
+const LINE_LEN: usize = 32;
+const SYMBOL_LEN: usize = 16;
+const FILE_LEN: usize = 8;
+
+/// The offset of a string missing from the cache, as the mangled name of a symbol that
+/// wasn't demangled.
+const NO_STRING: u32 = u32::MAX;
+
+/// A symbol cache: the functions, inlined functions, line rows and symbols of a binary image,
+/// as indexed by a [`Symbolicator`] and its symbol map, in a compact format to be read in place
+/// from a mapped file.
+///
+/// A cache is written once per binary image, keyed by its UUID, build ID or PDB GUID and its
+/// architecture, and symbolicates addresses as the [`Image`](crate::Image) it was written from
+/// does, without parsing any DWARF.
+///
+/// The file starts with the magic, followed by little-endian `u32`s: [`SYMCACHE_VERSION`], the
+/// number of functions, inlined functions, line rows, symbols and files, and the length of the
+/// strings. The records of each follow, with a fixed size each, the addresses of functions,
+/// line rows and symbols sorted for lookups to be a binary search, and then the strings.
+#[derive(Debug)]
+pub struct SymCache<'data> {
+    functions: &'data [u8],
+    inlined: &'data [u8],
+    lines: &'data [u8],
+    symbols: &'data [u8],
+    files: &'data [u8],
+    strings: &'data [u8],
+}
+
+/// A function of a [`SymCache`], or one of the ranges of a function with several.
+struct CachedFunction {
+    range: Range<u64>,
+    name: (u32, u32),
+    mangled_name: (u32, u32),
+    inlined: Range<usize>,
+}
+
+impl<'data> SymCache<'data> {
+    /// The name of the cache of `obj`, from its UUID, build ID or PDB GUID and age, and its
+    /// architecture, if it has any of them.
+    pub fn file_name(obj: &object::File) -> Result<Option<String>, Error> {
+        let id = match (obj.mach_uuid()?, obj.build_id()?, obj.pdb_info()?) {
+            (Some(uuid), _, _) => debug_file::hex(&uuid),
+            (None, Some(build_id), _) => debug_file::hex(build_id),
+            (None, None, Some(code_view)) => {
+                format!(
+                    "{}{:x}",
+                    debug_file::hex(&code_view.guid()),
+                    code_view.age()
+                )
+            }
+            (None, None, None) => return Ok(None),
+        };
+
+        Ok(Some(format!(
+            "{}-{}.symcache",
+            id,
+            obj.architecture().name()
+        )))
+    }
+
+    /// Writes the cache of `obj`, with its DWARF indexed by `symbolicator`, to `writer`.
+    pub fn write<W: io::Write>(
+        obj: &object::File,
+        symbolicator: &Symbolicator,
+        mut writer: W,
+    ) -> Result<(), Error> {
+        let mut strings = Strings::default();
+        let mut files = Strings::default();
+
+        let mut functions = Vec::default();
+        let mut inlined = Vec::default();
+        let mut lines = Vec::default();
+
+        for function in symbolicator.functions()? {
+            let name = strings.add(&function.name)?;
+            let mangled_name = strings.add_opt(function.mangled_name.as_deref())?;
+
+            let inlined_start = inlined.len() / INLINED_LEN;
+            for inlined_function in &function.inlined {
+                let name = strings.add(&inlined_function.name)?;
+                let mangled_name = strings.add_opt(inlined_function.mangled_name.as_deref())?;
+                let file = files.index(&inlined_function.call_loc.file.to_string_lossy())?;
+
+                for range in &inlined_function.ranges {
+                    put_u64(&mut inlined, range.start);
+                    put_u64(&mut inlined, range.end);
+                    put_u32(&mut inlined, name.0);
+                    put_u32(&mut inlined, name.1);
+                    put_u32(&mut inlined, mangled_name.0);
+                    put_u32(&mut inlined, mangled_name.1);
+                    put_u32(&mut inlined, file);
+                    put_u32(&mut inlined, to_u32(inlined_function.call_loc.line)?);
+                    put_u32(&mut inlined, to_u32(inlined_function.call_loc.col)?);
+                    put_u32(&mut inlined, 0);
+                }
+            }
+            let inlined_end = inlined.len() / INLINED_LEN;
+
+            for range in &function.ranges {
+                functions.push(CachedFunction {
+                    range: range.clone(),
+                    name,
+                    mangled_name,
+                    inlined: inlined_start..inlined_end,
+                });
+            }
+
+            for (range, loc) in function.lines {
+                let file = files.index(&loc.file.to_string_lossy())?;
+                lines.push((range, file, to_u32(loc.line)?, to_u32(loc.col)?));
+            }
+        }
+
+        functions.sort_by_key(|function| function.range.start);
+        lines.sort_by_key(|(range, ..)| range.start);
+        lines.dedup_by_key(|(range, ..)| range.start);
+
+        let symbol_map = obj.symbol_map();
+        let symbols = symbol_map
+            .symbols()
+            .iter()
+            .map(|symbol| Ok((symbol.address(), strings.add(symbol.name())?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let mut data = Vec::with_capacity(HEADER_LEN);
+        data.extend_from_slice(MAGIC);
+        put_u32(&mut data, SYMCACHE_VERSION);
+        put_u32(&mut data, to_u32(functions.len())?);
+        put_u32(&mut data, to_u32(inlined.len() / INLINED_LEN)?);
+        put_u32(&mut data, to_u32(lines.len())?);
+        put_u32(&mut data, to_u32(symbols.len())?);
+        put_u32(&mut data, to_u32(files.offsets.len())?);
+        put_u32(&mut data, to_u32(strings.data.len() + files.data.len())?);
+        put_u32(&mut data, 0);
+
+        for function in &functions {
+            put_u64(&mut data, function.range.start);
+            put_u64(&mut data, function.range.end);
+            put_u32(&mut data, function.name.0);
+            put_u32(&mut data, function.name.1);
+            put_u32(&mut data, function.mangled_name.0);
+            put_u32(&mut data, function.mangled_name.1);
+            put_u32(&mut data, to_u32(function.inlined.start)?);
+            put_u32(&mut data, to_u32(function.inlined.end)?);
+        }
+
+        data.extend_from_slice(&inlined);
+
+        for (range, file, line, col) in &lines {
+            put_u64(&mut data, range.start);
+            put_u64(&mut data, range.end);
+            put_u32(&mut data, *file);
+            put_u32(&mut data, *line);
+            put_u32(&mut data, *col);
+            put_u32(&mut data, 0);
+        }
+
+        for (addr, name) in &symbols {
+            put_u64(&mut data, *addr);
+            put_u32(&mut data, name.0);
+            put_u32(&mut data, name.1);
+        }
+
+        // File paths are kept in their own strings, as they're indexed by line rows.
+        let files_offset = to_u32(strings.data.len())?;
+        for (offset, len) in &files.offsets {
+            put_u32(
+                &mut data,
+                files_offset
+                    .checked_add(*offset)
+                    .ok_or(Error::SymCacheTooLarge)?,
+            );
+            put_u32(&mut data, *len);
+        }
+
+        data.extend_from_slice(&strings.data);
+        data.extend_from_slice(&files.data);
+
+        Ok(writer.write_all(&data)?)
+    }
+
+    /// Reads the cache in `data`, failing if it isn't one, or of another version.
+    pub fn parse(data: &'data [u8]) -> Result<Self, Error> {
+        if data.len() < HEADER_LEN || &data[..MAGIC.len()] != MAGIC {
+            return Err(Error::SymCacheInvalid);
+        }
+
+        let version = u32_at(data, 8);
+        if version != SYMCACHE_VERSION {
+            return Err(Error::SymCacheVersion(version));
+        }
+
+        let count = |offset: usize| u32_at(data, offset) as usize;
+        let lens = [
+            count(12) * FUNCTION_LEN,
+            count(16) * INLINED_LEN,
+            count(20) * LINE_LEN,
+            count(24) * SYMBOL_LEN,
+            count(28) * FILE_LEN,
+            count(32),
+        ];
+
+        let mut sections = Vec::with_capacity(lens.len());
+        let mut rest = &data[HEADER_LEN..];
+        for len in lens {
+            if rest.len() < len {
+                return Err(Error::SymCacheInvalid);
+            }
+
+            let (section, remaining) = rest.split_at(len);
+            sections.push(section);
+            rest = remaining;
+        }
+
+        Ok(Self {
+            functions: sections[0],
+            inlined: sections[1],
+            lines: sections[2],
+            symbols: sections[3],
+            files: sections[4],
+            strings: sections[5],
+        })
+    }
+
+    /// Symbolicates `addr`, returning the innermost frame first when `include_inlined` is set,
+    /// as [`Image::atos`](crate::Image::atos) does.
+    pub fn atos(&self, addr: Addr, include_inlined: bool) -> Result<Frames, Error> {
+        let function = partition_point(self.functions.len() / FUNCTION_LEN, |index| {
+            u64_at(self.functions, index * FUNCTION_LEN) <= *addr
+        })
+        .checked_sub(1)
+        .map(|index| self.function(index))
+        .filter(|function| function.range.contains(&*addr));
+
+        let line = partition_point(self.lines.len() / LINE_LEN, |index| {
+            u64_at(self.lines, index * LINE_LEN) <= *addr
+        })
+        .checked_sub(1)
+        .map(|index| &self.lines[index * LINE_LEN..][..LINE_LEN])
+        .filter(|line| *addr < u64_at(line, 8));
+
+        let (Some(function), Some(line)) = (function, line) else {
+            return Ok(Frames {
+                source: Source::SymbolTable,
+                symbols: atos_symbols(
+                    self.symbols.chunks_exact(SYMBOL_LEN).map(|symbol| {
+                        (
+                            u64_at(symbol, 0),
+                            self.str(u32_at(symbol, 8), u32_at(symbol, 12)),
+                        )
+                    }),
+                    addr,
+                )?,
+            });
+        };
+
+        let mut symbols = Vec::default();
+        let mut parent = (function.name, function.mangled_name);
+
+        if include_inlined {
+            for inlined in self
+                .inlined(&function)?
+                .chunks_exact(INLINED_LEN)
+                .filter(|inlined| (u64_at(inlined, 0)..u64_at(inlined, 8)).contains(&*addr))
+            {
+                // Every frame but the subprogram's, pushed first, was inlined into its caller.
+                symbols.push(self.symbol(
+                    addr,
+                    parent,
+                    SourceLoc {
+                        file: self.file(u32_at(inlined, 32)),
+                        line: u32_at(inlined, 36) as u64,
+                        col: u32_at(inlined, 40) as u64,
+                    },
+                    !symbols.is_empty(),
+                ));
+
+                parent = (
+                    (u32_at(inlined, 16), u32_at(inlined, 20)),
+                    (u32_at(inlined, 24), u32_at(inlined, 28)),
+                );
+            }
+        }
+
+        symbols.push(self.symbol(
+            addr,
+            parent,
+            SourceLoc {
+                file: self.file(u32_at(line, 16)),
+                line: u32_at(line, 20) as u64,
+                col: u32_at(line, 24) as u64,
+            },
+            !symbols.is_empty(),
+        ));
+
+        symbols.reverse();
+
+        Ok(Frames { source: Source::Dwarf, symbols })
+    }
+
+    /// Checks the records of the cache only refer to records within it, failing with
+    /// [`Error::SymCacheInvalid`] for a cache corrupted past its header, which [`Self::parse`]
+    /// doesn't read.
+    pub fn check(&self) -> Result<(), Error> {
+        for index in 0..self.functions.len() / FUNCTION_LEN {
+            self.inlined(&self.function(index))?;
+        }
+
+        Ok(())
+    }
+
+    /// The records of the functions inlined into `function`.
+    fn inlined(&self, function: &CachedFunction) -> Result<&'data [u8], Error> {
+        self.inlined
+            .get(function.inlined.start * INLINED_LEN..function.inlined.end * INLINED_LEN)
+            .ok_or(Error::SymCacheInvalid)
+    }
+
+    fn function(&self, index: usize) -> CachedFunction {
+        let record = &self.functions[index * FUNCTION_LEN..][..FUNCTION_LEN];
+        CachedFunction {
+            range: u64_at(record, 0)..u64_at(record, 8),
+            name: (u32_at(record, 16), u32_at(record, 20)),
+            mangled_name: (u32_at(record, 24), u32_at(record, 28)),
+            inlined: u32_at(record, 32) as usize..u32_at(record, 36) as usize,
+        }
+    }
+
+    fn symbol(
+        &self,
+        addr: Addr,
+        (name, mangled_name): ((u32, u32), (u32, u32)),
+        loc: SourceLoc,
+        inlined: bool,
+    ) -> Symbol {
+        Symbol {
+            addr,
+            name: self.str(name.0, name.1).to_owned(),
+            mangled_name: (mangled_name.0 != NO_STRING)
+                .then(|| self.str(mangled_name.0, mangled_name.1).to_owned()),
+            loc: Either::Left(loc),
+            inlined,
+        }
+    }
+
+    fn file(&self, index: u32) -> PathBuf {
+        self.files
+            .get(index as usize * FILE_LEN..)
+            .map(|file| PathBuf::from(self.str(u32_at(file, 0), u32_at(file, 4))))
+            .unwrap_or_default()
+    }
+
+    fn str(&self, offset: u32, len: u32) -> &'data str {
+        self.strings
+            .get(offset as usize..)
+            .and_then(|strings| strings.get(..len as usize))
+            .and_then(|string| str::from_utf8(string).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// The strings of a [`SymCache`] being written, each written once.
+#[derive(Default)]
+struct Strings {
+    data: Vec<u8>,
+    offsets: Vec<(u32, u32)>,
+    indexes: HashMap<String, usize>,
+}
+
+impl Strings {
+    /// Adds `string`, returning its offset and length.
+    fn add(&mut self, string: &str) -> Result<(u32, u32), Error> {
+        let index = self.index(string)?;
+        Ok(self.offsets[index as usize])
+    }
+
+    /// Adds `string`, returning the number of strings added before it.
+    fn index(&mut self, string: &str) -> Result<u32, Error> {
+        if let Some(index) = self.indexes.get(string) {
+            return to_u32(*index);
+        }
+
+        self.offsets
+            .push((to_u32(self.data.len())?, to_u32(string.len())?));
+        self.data.extend_from_slice(string.as_bytes());
+        self.indexes
+            .insert(string.to_owned(), self.offsets.len() - 1);
+
+        to_u32(self.offsets.len() - 1)
+    }
+
+    fn add_opt(&mut self, string: Option<&str>) -> Result<(u32, u32), Error> {
+        string.map_or(Ok((NO_STRING, 0)), |string| self.add(string))
+    }
+}
+
+/// `value` as a field of a symbol cache, failing if it doesn't fit in one.
+fn to_u32<T>(value: T) -> Result<u32, Error>
+where
+    u32: TryFrom<T>,
+{
+    u32::try_from(value).map_err(|_| Error::SymCacheTooLarge)
+}
+
+/// The number of the `len` records for which `pred` holds, as [`slice::partition_point`].
+fn partition_point(len: usize, pred: impl Fn(usize) -> bool) -> usize {
+    let (mut low, mut high) = (0, len);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if pred(mid) {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+
+    low
+}
+
+fn put_u32(data: &mut Vec<u8>, value: u32) {
+    data.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u64(data: &mut Vec<u8>, value: u64) {
+    data.extend_from_slice(&value.to_le_bytes());
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    data.get(offset..offset + 4).map_or(0, |bytes| {
+        u32::from_le_bytes(bytes.try_into().unwrap_or_default())
+    })
+}
+
+fn u64_at(data: &[u8], offset: usize) -> u64 {
+    data.get(offset..offset + 8).map_or(0, |bytes| {
+        u64::from_le_bytes(bytes.try_into().unwrap_or_default())
+    })
+}
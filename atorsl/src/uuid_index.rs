@@ -0,0 +1,144 @@
+use crate::{data::*, ext::object::File as _};
+use memmap2::Mmap;
+use object::{macho, Object};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+/// The Mach-O binary images found in a set of directories, by the `LC_UUID` of each of their
+/// architectures.
+///
+/// Directories are walked recursively, so the DWARF files of `.dSYM` bundles are found
+/// wherever they are nested, as in the `dSYMs` folder of an `.xcarchive`, along with loose
+/// binary images. Symbolic links aren't followed, for links to a parent directory not to be
+/// walked forever.
+#[derive(Debug, Default)]
+pub struct UuidIndex {
+    images: HashMap<[u8; 16], Vec<IndexedImage>>,
+
+    /// The entries of the directories that couldn't be read, and why.
+    skipped: Vec<(PathBuf, io::Error)>,
+}
+
+/// An architecture of a binary image found by [`UuidIndex`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IndexedImage {
+    pub path: PathBuf,
+    pub arch: Arch,
+
+    /// Whether the binary image has DWARF, as the DWARF file of a `.dSYM` does.
+    pub has_dwarf: bool,
+}
+
+impl UuidIndex {
+    /// Indexes the binary images in `dirs` and their subdirectories.
+    ///
+    /// Files that aren't Mach-O binary images, or can't be read, are skipped, as are
+    /// subdirectories that can't be read, which are listed by [`skipped`](Self::skipped).
+    pub fn index<P: AsRef<Path>>(dirs: &[P]) -> Result<Self, Error> {
+        let mut index = Self::default();
+        for dir in dirs {
+            index.index_dir(dir.as_ref())?;
+        }
+
+        for images in index.images.values_mut() {
+            images.sort_by_key(|image| !image.has_dwarf);
+        }
+
+        Ok(index)
+    }
+
+    /// Finds the binary image with `uuid`, preferring one with DWARF over one without it.
+    pub fn find(&self, uuid: &[u8; 16]) -> Option<&IndexedImage> {
+        self.images.get(uuid)?.first()
+    }
+
+    /// The subdirectories and entries of the directories indexed that couldn't be read, along
+    /// with the error reading each.
+    pub fn skipped(&self) -> &[(PathBuf, io::Error)] {
+        &self.skipped
+    }
+
+    /// Every binary image found with `uuid`, the ones with DWARF first.
+    pub fn find_all(&self, uuid: &[u8; 16]) -> &[IndexedImage] {
+        self.images
+            .get(uuid)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    fn index_dir(&mut self, dir: &Path) -> io::Result<()> {
+        let mut entries = fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        entries.sort();
+
+        for path in entries {
+            let metadata = match fs::symlink_metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(err) => {
+                    self.skipped.push((path, err));
+                    continue;
+                }
+            };
+
+            if metadata.is_dir() {
+                if let Err(err) = self.index_dir(&path) {
+                    self.skipped.push((path, err));
+                }
+            } else if metadata.is_file() && is_macho(&path) {
+                self.index_file(path);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn index_file(&mut self, path: PathBuf) {
+        let Ok(mmap) = fs::File::open(&path).and_then(|file| unsafe { Mmap::map(&file) }) else {
+            return;
+        };
+
+        let Ok(objs) = object::File::parse_slices(&mmap) else {
+            return;
+        };
+
+        for (arch, obj) in objs {
+            if let Ok(Some(uuid)) = obj.mach_uuid() {
+                self.images
+                    .entry(uuid)
+                    .or_default()
+                    .push(IndexedImage {
+                        path: path.clone(),
+                        arch,
+                        has_dwarf: obj.section_by_name("__debug_info").is_some(),
+                    });
+            }
+        }
+    }
+}
+
+/// Whether the file at `path` starts with the magic of a Mach-O or universal binary.
+fn is_macho(path: &Path) -> bool {
+    let mut magic = [0; 4];
+    if fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_err()
+    {
+        return false;
+    }
+
+    [
+        macho::MH_MAGIC,
+        macho::MH_CIGAM,
+        macho::MH_MAGIC_64,
+        macho::MH_CIGAM_64,
+        macho::FAT_MAGIC,
+        macho::FAT_MAGIC_64,
+    ]
+    .contains(&u32::from_be_bytes(magic))
+}
@@ -0,0 +1,47 @@
+use atorsl::{data::Addr, AddrInput, Error};
+
+#[test]
+fn test_addr_input_tokens() {
+    let input = "0x10\t0X20 # 0x30\r\n\n  40 ff bogus\n# 0x50\n0x60";
+    let addrs = AddrInput::Tokens
+        .read(input.as_bytes())
+        .map(|addr| addr.map_err(|err| err.to_string()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        addrs,
+        [
+            Ok(Addr::from(0x10)),
+            Ok(Addr::from(0x20)),
+            Ok(Addr::from(40)),
+            Ok(Addr::from(0xff)),
+            Err(Error::AddrInputInvalid(3, String::from("bogus")).to_string()),
+            Ok(Addr::from(0x60)),
+        ]
+    );
+}
+
+#[test]
+fn test_addr_input_extract() {
+    let input = "\
+        3   libfoo.dylib   0x00000001000a1b2c foo + 12\n\
+        frame #3: 0x0000000100003f70 a.out`main + 16 at main.c:3\n\
+        #1  0x0000555555555171 in main () at t.c:5\n\
+        Thread 0 Crashed: 0xa0 dummy\n\
+        no address at all, not even hex0x10 or 0x\n";
+
+    let addrs = AddrInput::Extract
+        .read(input.as_bytes())
+        .collect::<Result<Vec<_>, _>>()
+        .expect("addresses to be extracted");
+
+    assert_eq!(
+        addrs,
+        [
+            Addr::from(0x1000a1b2c),
+            Addr::from(0x100003f70),
+            Addr::from(0x555555555171),
+            Addr::from(0xa0),
+        ]
+    );
+}
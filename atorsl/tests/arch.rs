@@ -0,0 +1,84 @@
+use atorsl::{data::Arch, ext::object::File as _, Error};
+use object::macho::{CPU_SUBTYPE_ARM64E, CPU_SUBTYPE_ARM64_ALL, CPU_TYPE_ARM64, FAT_MAGIC};
+use std::fs;
+
+mod common;
+use common::fixture_path;
+
+/// The alignment of the slices of the universal binary, as a power of two.
+const SLICE_ALIGN: u32 = 12;
+
+/// A universal binary with a slice for each of `cpus`, as CPU type and subtype, all of them
+/// the same x86_64 binary image, as only the header of the universal binary is read to select
+/// a slice.
+fn universal_binary(cpus: &[(u32, u32)]) -> Vec<u8> {
+    let slice = fs::read(fixture_path("inlined_macho")).unwrap();
+    let offset = 1 << SLICE_ALIGN;
+
+    let mut data = [FAT_MAGIC, cpus.len() as u32]
+        .iter()
+        .flat_map(|field| field.to_be_bytes())
+        .collect::<Vec<_>>();
+
+    for (cputype, cpusubtype) in cpus {
+        data.extend(
+            [
+                *cputype,
+                *cpusubtype,
+                offset,
+                slice.len() as u32,
+                SLICE_ALIGN,
+            ]
+            .iter()
+            .flat_map(|field| field.to_be_bytes()),
+        );
+    }
+
+    data.resize(offset as usize, 0);
+    data.extend(slice);
+    data
+}
+
+#[test]
+fn test_arch_name() {
+    for name in [
+        "i386", "x86_64", "x86_64h", "armv7", "armv7s", "arm64", "arm64e",
+    ] {
+        assert_eq!(Arch::from_name(name).name(), name);
+    }
+
+    assert_eq!(Arch::from_name("aarch64"), Arch::from_name("arm64"));
+    assert_ne!(Arch::from_name("arm64e"), Arch::from_name("arm64"));
+}
+
+#[test]
+fn test_parse_slice() -> Result<(), Error> {
+    let data = universal_binary(&[
+        (CPU_TYPE_ARM64, CPU_SUBTYPE_ARM64_ALL),
+        (CPU_TYPE_ARM64, 0x8000_0000 | CPU_SUBTYPE_ARM64E),
+    ]);
+
+    let selected_arch = |name: &str| {
+        object::File::parse_slice(&data, Some(Arch::from_name(name))).map(|(arch, _)| arch.name())
+    };
+
+    assert_eq!(object::File::parse_slice(&data, None)?.0.name(), "arm64");
+    assert_eq!(selected_arch("arm64")?, "arm64");
+    assert_eq!(selected_arch("arm64e")?, "arm64e");
+
+    assert!(matches!(
+        selected_arch("armv7"),
+        Err(Error::CannotLoadSymbolsForArch(_, found)) if found == "arm64, arm64e"
+    ));
+
+    // A slice of a subtype is selected for the architecture naming every subtype of its CPU.
+    let data = universal_binary(&[(CPU_TYPE_ARM64, CPU_SUBTYPE_ARM64E)]);
+    assert_eq!(
+        object::File::parse_slice(&data, Some(Arch::from_name("arm64")))?
+            .0
+            .name(),
+        "arm64e"
+    );
+
+    Ok(())
+}
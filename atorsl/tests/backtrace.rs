@@ -0,0 +1,150 @@
+use atorsl::{
+    data::{Addr, Loc, Offset, SourceLoc, Symbol},
+    BacktraceFrame,
+};
+use itertools::Either;
+use std::path::PathBuf;
+
+fn symbols() -> [Symbol; 2] {
+    [
+        Symbol {
+            addr: Addr::from(0x1000),
+            name: String::from("square"),
+            mangled_name: None,
+            loc: Either::Left(SourceLoc {
+                file: PathBuf::from("/src/inlined.c"),
+                line: 5,
+                col: 44,
+            }),
+            inlined: true,
+        },
+        Symbol {
+            addr: Addr::from(0x1000),
+            name: String::from("compute"),
+            mangled_name: None,
+            loc: Either::Right(Offset::from(6usize)),
+            inlined: false,
+        },
+    ]
+}
+
+fn rewrite(line: &str) -> Option<(BacktraceFrame, String)> {
+    let frame = BacktraceFrame::parse(line)?;
+    let rewritten = frame.rewrite(line, &symbols());
+    Some((frame, rewritten))
+}
+
+#[test]
+fn test_backtrace_apple() {
+    let (frame, rewritten) =
+        rewrite("3   MyApp    0x0000000100369e4c 0x100360000 + 40524").expect("frame");
+
+    assert_eq!(frame.image.as_deref(), Some("MyApp"));
+    assert!(frame.image_truncated);
+    assert_eq!(frame.addr, Addr::from(0x100369e4c));
+    assert_eq!(frame.base_addr, Some(Loc::Load(Addr::from(0x100360000))));
+    assert_eq!(
+        rewritten,
+        "3   MyApp    0x0000000100369e4c square (inlined.c:5)\n\
+        3   MyApp    0x0000000100369e4c compute + 6"
+    );
+
+    let (frame, _) = rewrite("3   MyApp    0x0000000100369e4c main + 28").expect("frame");
+    assert_eq!(frame.base_addr, None);
+}
+
+#[test]
+fn test_backtrace_glibc() {
+    let (frame, rewritten) = rewrite("./myapp(main+0x1c) [0x55d5c2a3b4c5]").expect("frame");
+
+    assert_eq!(frame.image.as_deref(), Some("./myapp"));
+    assert!(!frame.image_truncated);
+    assert_eq!(frame.addr, Addr::from(0x55d5c2a3b4c5));
+    assert_eq!(
+        rewritten,
+        "./myapp(square (inlined.c:5)) [0x55d5c2a3b4c5]\n\
+        ./myapp(compute + 6) [0x55d5c2a3b4c5]"
+    );
+
+    let (_, rewritten) = rewrite("./myapp() [0x4008ed]").expect("frame");
+    assert!(rewritten.starts_with("./myapp(square (inlined.c:5)) [0x4008ed]"));
+}
+
+#[test]
+fn test_backtrace_rust() {
+    let (frame, rewritten) =
+        rewrite("  10:     0x55d5c2a3b4c5 - myapp::main::h1234").expect("frame");
+
+    assert_eq!(frame.image, None);
+    assert_eq!(frame.addr, Addr::from(0x55d5c2a3b4c5));
+    assert_eq!(
+        rewritten.lines().next(),
+        Some("  10:     0x55d5c2a3b4c5 - square (inlined.c:5)")
+    );
+
+    let (_, rewritten) = rewrite("   3: 0x4008ed").expect("frame");
+    assert_eq!(
+        rewritten.lines().last(),
+        Some("   3: 0x4008ed - compute + 6")
+    );
+
+    assert_eq!(BacktraceFrame::parse("   3: myapp::main"), None);
+}
+
+#[test]
+fn test_backtrace_android() {
+    let (frame, rewritten) =
+        rewrite("    #03 pc 0000000000042f1c  /system/lib64/libfoo.so (foo+164) (BuildId: ab12)")
+            .expect("frame");
+
+    assert_eq!(frame.image.as_deref(), Some("/system/lib64/libfoo.so"));
+    assert_eq!(frame.addr, Addr::from(0x42f1c));
+    assert_eq!(frame.base_addr, Some(Loc::Slide(Addr::nil())));
+    assert_eq!(
+        rewritten.lines().next(),
+        Some("    #03 pc 0000000000042f1c  /system/lib64/libfoo.so (square (inlined.c:5)) (BuildId: ab12)")
+    );
+
+    let (_, rewritten) =
+        rewrite("    #03 pc 0000000000042f1c  /system/lib64/libfoo.so (BuildId: ab12)")
+            .expect("frame");
+    assert_eq!(
+        rewritten.lines().last(),
+        Some("    #03 pc 0000000000042f1c  /system/lib64/libfoo.so (compute + 6) (BuildId: ab12)")
+    );
+}
+
+#[test]
+fn test_backtrace_android_cpp() {
+    let (frame, rewritten) = rewrite(
+        "    #05 pc 000000000001d2a4  /data/app/base.apk (offset 0x1000) (foo(int)+12) (BuildId: ab12)",
+    )
+    .expect("frame");
+
+    assert_eq!(frame.image.as_deref(), Some("/data/app/base.apk"));
+    assert_eq!(frame.addr, Addr::from(0x1d2a4));
+    assert_eq!(
+        rewritten.lines().next(),
+        Some("    #05 pc 000000000001d2a4  /data/app/base.apk (offset 0x1000) (square (inlined.c:5)) (BuildId: ab12)")
+    );
+
+    let (_, rewritten) =
+        rewrite("    #05 pc 000000000001d2a4  /data/app/base.apk (offset 0x1000)").expect("frame");
+    assert_eq!(
+        rewritten.lines().last(),
+        Some("    #05 pc 000000000001d2a4  /data/app/base.apk (offset 0x1000) (compute + 6)")
+    );
+}
+
+#[test]
+fn test_backtrace_other_lines() {
+    for line in [
+        "",
+        "Thread 0 Crashed:",
+        "Exception Type:  EXC_BAD_ACCESS (SIGSEGV)",
+        "backtrace: (see below) [0xzz]",
+        "    at ./src/main.rs:5:5",
+    ] {
+        assert_eq!(BacktraceFrame::parse(line), None, "{line}");
+    }
+}
@@ -0,0 +1,120 @@
+use atorsl::{
+    breakpad,
+    data::{Addr, Source},
+    load_dwarf, BreakpadDump, BreakpadSym, Error, Symbolicator,
+};
+
+mod common;
+use common::fixture;
+
+fn sym() -> Result<BreakpadSym, Error> {
+    BreakpadSym::parse(&String::from_utf8_lossy(&fixture("inlined_elf.sym")))
+}
+
+#[test]
+fn test_breakpad_dump() -> Result<(), Error> {
+    let data = fixture("inlined_elf");
+    let obj = object::File::parse(&*data)?;
+    let cow;
+    let dwarf = load_dwarf!(&obj, cow);
+
+    assert_eq!(
+        breakpad::dump(&obj, &Symbolicator::new(&dwarf)?, "inlined_elf")?,
+        BreakpadDump {
+            sym: String::from_utf8_lossy(&fixture("inlined_elf.sym")).into_owned(),
+            skipped: Vec::default(),
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_breakpad_sym_atos() -> Result<(), Error> {
+    let sym = sym()?;
+    assert_eq!(sym.module.os, "Linux");
+    assert_eq!(sym.module.name, "inlined_elf");
+    assert_eq!(sym.base_addr, Addr::from(0x400000));
+
+    let frames = sym.atos(Addr::from(0x401196), true)?;
+    assert_eq!(frames.source, Source::Breakpad);
+    assert_eq!(
+        frames
+            .symbols
+            .iter()
+            .map(|symbol| (
+                symbol.name.as_str(),
+                symbol.loc.as_ref().left().map(|loc| loc.line),
+                symbol.inlined
+            ))
+            .collect::<Vec<_>>(),
+        [
+            ("square", Some(5), true),
+            ("sum_of_squares", Some(7), true),
+            ("compute", Some(12), false),
+        ]
+    );
+
+    let frames = sym.atos(Addr::from(0x401196), false)?;
+    assert_eq!(frames.symbols.len(), 1);
+    assert_eq!(frames.symbols[0].name, "compute");
+    assert_eq!(
+        frames.symbols[0]
+            .loc
+            .as_ref()
+            .left()
+            .map(|loc| loc.line),
+        Some(5)
+    );
+
+    let frames = sym.atos(Addr::from(0x401095), true)?;
+    assert_eq!(frames.source, Source::SymbolTable);
+    assert_eq!(frames.symbols[0].name, "_start");
+    assert_eq!(
+        frames.symbols[0]
+            .loc
+            .as_ref()
+            .right()
+            .map(|offset| **offset),
+        Some(5)
+    );
+
+    assert!(matches!(
+        sym.atos(Addr::from(0x10), true),
+        Err(Error::AddrNotFound(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_breakpad_sym_without_base_address() -> Result<(), Error> {
+    // As written by Breakpad's `dump_syms`, whose addresses are offsets into the binary image.
+    let sym = BreakpadSym::parse(
+        &String::from_utf8_lossy(&fixture("inlined_elf.sym"))
+            .lines()
+            .filter(|line| !line.starts_with("INFO BASE_ADDRESS "))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )?;
+    assert_eq!(sym.base_addr, Addr::from(0));
+
+    assert_eq!(
+        sym.atos(Addr::from(0x1196), false)?
+            .symbols
+            .iter()
+            .map(|symbol| symbol.name.as_str())
+            .collect::<Vec<_>>(),
+        ["compute"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_breakpad_sym_invalid() {
+    assert!(matches!(
+        BreakpadSym::parse("MODULE Linux x86_64 0 a\nFUNC 10 zz 0 f\n"),
+        Err(Error::BreakpadSymInvalid(2))
+    ));
+}
@@ -0,0 +1,22 @@
+#![allow(dead_code)]
+
+use std::{fs, path::PathBuf};
+
+/// The path to `path` in the fixtures.
+pub fn fixtures_path(path: &str) -> PathBuf {
+    PathBuf::from(format!(
+        "{}/../fixtures/{}",
+        env!("CARGO_MANIFEST_DIR"),
+        path
+    ))
+}
+
+/// The path to the object `name` in the fixtures.
+pub fn fixture_path(name: &str) -> PathBuf {
+    fixtures_path(&format!("objects/{name}"))
+}
+
+/// The contents of the object `name` in the fixtures.
+pub fn fixture(name: &str) -> Vec<u8> {
+    fs::read(fixture_path(name)).expect("fixture to be readable")
+}
@@ -0,0 +1,115 @@
+use atorsl::{
+    data::{Addr, Offset, Symbol},
+    CrashImage, CrashReport, Error,
+};
+use itertools::Either;
+use serde_json::Value;
+use std::fs;
+
+mod common;
+use common::fixtures_path;
+
+const UUID: [u8; 16] = [
+    0x4c, 0x4c, 0x44, 0xca, 0x55, 0x55, 0x31, 0x44, 0xa1, 0x47, 0x1f, 0xd6, 0x51, 0x4c, 0x28, 0x82,
+];
+
+fn crash_report(name: &str) -> String {
+    fs::read_to_string(fixtures_path("crash_reports").join(name)).expect("fixture to be readable")
+}
+
+fn symbol(name: &str, offset: u64) -> Symbol {
+    Symbol {
+        addr: Addr::nil(),
+        name: name.to_owned(),
+        mangled_name: None,
+        loc: Either::Right(Offset::from(offset)),
+        inlined: false,
+    }
+}
+
+#[test]
+fn test_crash_report_images() -> Result<(), Error> {
+    for name in ["inlined_macho.crash", "inlined_macho.ips"] {
+        let report = CrashReport::parse(&crash_report(name))?;
+        let images = report.images();
+
+        assert_eq!(images.len(), 2, "{name}");
+        assert_eq!(
+            images[0],
+            CrashImage {
+                name: String::from("inlined_macho"),
+                uuid: Some(UUID),
+                arch: images[0].arch.clone(),
+                path: Some(String::from("/Users/USER/bin/inlined_macho")),
+                load_addr: Addr::from(0x104a98000),
+            },
+            "{name}"
+        );
+        assert_eq!(images[1].name, "dyld", "{name}");
+        assert_eq!(images[1].load_addr, Addr::from(0x7ff80a5ce000), "{name}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_crash_report_symbolicate_text() -> Result<(), Error> {
+    let report = CrashReport::parse(&crash_report("inlined_macho.crash"))?;
+    let mut frames = Vec::default();
+
+    let symbolicated = report.symbolicate(|image, addr| {
+        frames.push((image, addr));
+        (addr == Addr::from(0x104a982d0)).then(|| vec![symbol("main", 0)])
+    })?;
+
+    assert_eq!(
+        frames,
+        [
+            (0, Addr::from(0x104a982b4)),
+            (0, Addr::from(0x104a982d0)),
+            (1, Addr::from(0x7ff80a5d441f)),
+        ]
+    );
+
+    let lines = symbolicated.lines().collect::<Vec<_>>();
+    assert!(lines
+        .contains(&"0   inlined_macho                 \t0x0000000104a982b4 0x104a98000 + 692"));
+    assert!(lines.contains(&"1   inlined_macho                 \t0x0000000104a982d0 main + 0"));
+    assert_eq!(
+        lines.len(),
+        crash_report("inlined_macho.crash").lines().count()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_crash_report_symbolicate_ips() -> Result<(), Error> {
+    let report = CrashReport::parse(&crash_report("inlined_macho.ips"))?;
+    let symbolicated = report.symbolicate(|image, addr| {
+        (image == 0).then(|| {
+            let mut inlined = symbol("inlined", 1);
+            inlined.inlined = true;
+            vec![inlined, symbol(&format!("{addr}"), 2)]
+        })
+    })?;
+
+    let (_, body) = symbolicated
+        .split_once('\n')
+        .expect("ips to have a header");
+    let body = serde_json::from_str::<Value>(body).expect("ips body to be JSON");
+    let frames = body["threads"][0]["frames"]
+        .as_array()
+        .expect("thread to have frames");
+
+    assert_eq!(frames.len(), 5);
+    assert_eq!(frames[0]["symbol"], "inlined");
+    assert_eq!(frames[0]["inline"], true);
+    assert_eq!(frames[1]["symbol"], "0x0000000104a982b4");
+    assert_eq!(frames[1]["symbolLocation"], 2);
+    assert!(frames[1].get("inline").is_none());
+    assert_eq!(frames[3]["symbol"], "0x0000000104a982d0");
+    assert_eq!(frames[4]["symbol"], "start");
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use atorsl::{debug_file, Error};
+use std::{fs, path::PathBuf};
+
+mod common;
+use common::fixtures_path;
+
+fn find(obj_path: &PathBuf, debug_dirs: &[PathBuf]) -> Result<Option<PathBuf>, Error> {
+    let data = fs::read(obj_path)?;
+    let obj = object::File::parse(&*data)?;
+    debug_file::find(&obj, obj_path, debug_dirs)
+}
+
+#[test]
+fn test_debug_file_debuglink() -> Result<(), Error> {
+    let obj_path = fixtures_path("objects/inlined_stripped");
+
+    assert_eq!(
+        find(&obj_path, &[])?
+            .map(fs::canonicalize)
+            .transpose()?,
+        Some(fs::canonicalize(fixtures_path(
+            "objects/inlined_stripped.debug"
+        ))?)
+    );
+
+    let dir = std::env::temp_dir().join("atorsl_test_debug_file_debuglink");
+    fs::create_dir_all(&dir)?;
+    fs::copy(&obj_path, dir.join("inlined_stripped"))?;
+    fs::write(dir.join("inlined_stripped.debug"), b"not the debug file")?;
+
+    assert_eq!(find(&dir.join("inlined_stripped"), &[])?, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_debug_file_build_id() -> Result<(), Error> {
+    let obj_path = fixtures_path("objects/inlined_stripped_build_id");
+
+    assert_eq!(find(&obj_path, &[])?, None);
+    assert_eq!(
+        find(&obj_path, &[fixtures_path("debug")])?,
+        Some(fixtures_path(
+            "debug/.build-id/a1/423422d5e4819d6389557b294103529a6fc66a.debug"
+        ))
+    );
+
+    Ok(())
+}
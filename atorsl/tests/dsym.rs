@@ -0,0 +1,71 @@
+use atorsl::{data::Arch, Dsym, Error};
+use std::path::Path;
+
+mod common;
+use common::fixture_path;
+
+const INLINED_MACHO_UUID: [u8; 16] = [
+    0x4c, 0x4c, 0x44, 0xca, 0x55, 0x55, 0x31, 0x44, 0xa1, 0x47, 0x1f, 0xd6, 0x51, 0x4c, 0x28, 0x82,
+];
+
+const INLINED_MACHO_ARCHIVE_UUID: [u8; 16] = [
+    0x4c, 0x4c, 0x44, 0xf1, 0x55, 0x55, 0x31, 0x44, 0xa1, 0x99, 0x18, 0x76, 0x08, 0x3e, 0xa9, 0x18,
+];
+
+fn file_name(path: &Path) -> &str {
+    path.file_name().unwrap().to_str().unwrap()
+}
+
+#[test]
+fn test_dsym_dwarf_files() -> Result<(), Error> {
+    // The bundle also has a `.DS_Store`, which is skipped.
+    let dsym = Dsym::open(&fixture_path("inlined_macho.framework.dSYM"))?;
+
+    assert_eq!(
+        dsym.dwarf_files()
+            .iter()
+            .map(|dwarf_file| (file_name(&dwarf_file.path), dwarf_file.slices.clone()))
+            .collect::<Vec<_>>(),
+        [
+            (
+                "inlined_macho",
+                vec![(Some(INLINED_MACHO_UUID), Arch::from_name("x86_64"))]
+            ),
+            (
+                "inlined_macho_archive",
+                vec![(Some(INLINED_MACHO_ARCHIVE_UUID), Arch::from_name("x86_64"))]
+            ),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_dsym_select() -> Result<(), Error> {
+    let dsym = Dsym::open(&fixture_path("inlined_macho.framework.dSYM"))?;
+
+    assert_eq!(file_name(dsym.select(None, None)?), "inlined_macho");
+    assert_eq!(
+        file_name(dsym.select(None, Some(Arch::from_name("x86_64")))?),
+        "inlined_macho"
+    );
+    assert_eq!(
+        file_name(dsym.select(Some(&INLINED_MACHO_ARCHIVE_UUID), None)?),
+        "inlined_macho_archive"
+    );
+
+    assert!(matches!(
+        dsym.select(Some(&[0; 16]), None),
+        Err(Error::DsymDwarfFileNotFound(_, candidates))
+            if candidates.contains("inlined_macho (4C4C44CA-5555-3144-A147-1FD6514C2882 x86_64)")
+                && candidates.contains("inlined_macho_archive")
+    ));
+
+    assert!(matches!(
+        dsym.select(None, Some(Arch::from_name("arm64"))),
+        Err(Error::DsymDwarfFileNotFound(..))
+    ));
+
+    Ok(())
+}
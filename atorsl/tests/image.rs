@@ -0,0 +1,203 @@
+use atorsl::{
+    data::{Addr, Loc, Source},
+    load_dwarf, Error, Image, SymbolQuery, Symbolicator,
+};
+use std::{num::NonZeroUsize, path::Path};
+
+mod common;
+use common::fixture;
+
+#[test]
+fn test_image_symbolicate() -> Result<(), Error> {
+    let data = fixture("inlined_elf");
+    let obj = object::File::parse(&*data)?;
+    let cow;
+    let dwarf = load_dwarf!(&obj, cow);
+    let image = Image::new(&obj, Symbolicator::new(&dwarf)?);
+
+    let symbolications = image
+        .symbolicate(
+            &Loc::Slide(Addr::from(0x1000)),
+            [0x402196, 0x402090, 0x10].map(Addr::from),
+            true,
+        )?
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        symbolications
+            .iter()
+            .map(|symbolication| symbolication.input_addr)
+            .collect::<Vec<_>>(),
+        [0x402196, 0x402090, 0x10].map(Addr::from)
+    );
+
+    assert_eq!(symbolications[0].addr, Some(Addr::from(0x401196)));
+    let frames = symbolications[0].result.as_ref().unwrap();
+    assert_eq!(frames.source, Source::Dwarf);
+    assert_eq!(
+        frames
+            .symbols
+            .iter()
+            .map(|symbol| symbol.name.as_str())
+            .collect::<Vec<_>>(),
+        ["square", "sum_of_squares", "compute"]
+    );
+
+    let frames = symbolications[1].result.as_ref().unwrap();
+    assert_eq!(frames.source, Source::SymbolTable);
+    assert_eq!(frames.symbols[0].name, "start");
+
+    assert!(matches!(
+        symbolications[2].result,
+        Err(Error::AddrInvalid(addr)) if addr == Addr::from(0x10)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_image_symbolicate_load_addr() -> Result<(), Error> {
+    let data = fixture("inlined_macho");
+    let obj = object::File::parse(&*data)?;
+    let cow;
+    let dwarf = load_dwarf!(&obj, cow);
+    let image = Image::new(&obj, Symbolicator::new(&dwarf)?);
+
+    let symbolication = image
+        .symbolicate(
+            &Loc::Load(Addr::from(0x200000000)),
+            [Addr::from(0x2000002d0)],
+            false,
+        )?
+        .next()
+        .unwrap();
+
+    assert_eq!(symbolication.addr, Some(Addr::from(0x1000002d0)));
+    let frames = symbolication.result?;
+    assert_eq!(frames.source, Source::SymbolTable);
+    assert_eq!(frames.symbols[0].name, "main");
+
+    assert!(matches!(
+        image.symbolicate(&Loc::Load(Addr::from(0x1000)), [], false),
+        Err(Error::LoadAddrInvalid(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_image_symbolicate_parallel() -> Result<(), Error> {
+    let data = fixture("inlined_elf");
+    let obj = object::File::parse(&*data)?;
+    let cow;
+    let dwarf = load_dwarf!(&obj, cow);
+    let image = Image::new(&obj, Symbolicator::new(&dwarf)?);
+
+    let base_addr = Loc::Slide(Addr::nil());
+    let addrs = (0x401000..0x401400)
+        .map(Addr::from)
+        .collect::<Vec<_>>();
+
+    let sequential = image
+        .symbolicate(&base_addr, addrs.iter().copied(), true)?
+        .map(|symbolication| (symbolication.input_addr, symbolication.result.ok()))
+        .collect::<Vec<_>>();
+
+    let parallel = image
+        .symbolicate_parallel(&base_addr, &addrs, true, NonZeroUsize::new(4).unwrap())?
+        .into_iter()
+        .map(|symbolication| (symbolication.input_addr, symbolication.result.ok()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(parallel, sequential);
+
+    Ok(())
+}
+
+#[test]
+fn test_image_lookup_symbol() -> Result<(), Error> {
+    let data = fixture("inlined_elf");
+    let obj = object::File::parse(&*data)?;
+    let cow;
+    let dwarf = load_dwarf!(&obj, cow);
+    let image = Image::new(&obj, Symbolicator::new(&dwarf)?);
+    let slide = Loc::Slide(Addr::from(0x1000));
+
+    let found = image.lookup_symbol(&slide, &SymbolQuery::new("square"))?;
+    assert_eq!(found.len(), 2);
+    assert!(found.iter().all(|found| found.source == Source::Dwarf));
+    assert_eq!(
+        found[0].ranges,
+        [
+            Addr::from(0x402192)..Addr::from(0x402194),
+            Addr::from(0x402196)..Addr::from(0x402199)
+        ]
+    );
+
+    let (caller, call_loc) = found[0].inlined_into.as_ref().unwrap();
+    assert_eq!(caller, "sum_of_squares");
+    assert_eq!(call_loc.line, 7);
+
+    let found = image.lookup_symbol(&slide, &SymbolQuery::new("comput.*|start"))?;
+    assert_eq!(
+        found
+            .iter()
+            .map(|found| (
+                found.name.as_str(),
+                found.source,
+                found.inlined_into.is_some()
+            ))
+            .collect::<Vec<_>>(),
+        [
+            ("compute", Source::Dwarf, false),
+            ("start", Source::SymbolTable, false)
+        ]
+    );
+    assert_eq!(
+        found[0].ranges,
+        [Addr::from(0x402180)..Addr::from(0x4021b5)]
+    );
+
+    assert!(image
+        .lookup_symbol(&slide, &SymbolQuery::new("sum_of"))?
+        .is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_image_lookup_line() -> Result<(), Error> {
+    let data = fixture("inlined_elf");
+    let obj = object::File::parse(&*data)?;
+    let cow;
+    let dwarf = load_dwarf!(&obj, cow);
+    let image = Image::new(&obj, Symbolicator::new(&dwarf)?);
+    let slide = Loc::Slide(Addr::from(0x1000));
+
+    let found = image.lookup_line(&slide, Path::new("src/inlined.c"), 7)?;
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].range, Addr::from(0x40219f)..Addr::from(0x4021a1));
+    assert_eq!(
+        found[0]
+            .symbols
+            .as_ref()
+            .expect("frames")
+            .iter()
+            .map(|symbol| (symbol.name.as_str(), *symbol.addr))
+            .collect::<Vec<_>>(),
+        [("sum_of_squares", 0x40219f), ("compute", 0x40219f)]
+    );
+
+    let found = image.lookup_line(&slide, Path::new("inlined.c"), 5)?;
+    assert_eq!(found.len(), 3);
+    assert!(found.iter().all(
+        |found| matches!(&found.symbols, Ok(symbols) if symbols[0].name == "square")
+            && found.loc.line == 5
+    ));
+
+    assert!(image
+        .lookup_line(&slide, Path::new("lined.c"), 5)?
+        .is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use atorsl::{
+    data::{Addr, Loc, Source, SourceLoc},
+    load_dwarf, Error, Image, PdbFile, Symbolicator,
+};
+use itertools::Either;
+use std::fs;
+
+mod common;
+use common::fixture_path;
+
+#[test]
+fn test_pdb_file_find() -> Result<(), Error> {
+    let obj_path = fixture_path("inlined_pe.exe");
+    let data = fs::read(&obj_path)?;
+    let obj = object::File::parse(&*data)?;
+
+    assert_eq!(
+        PdbFile::find(&obj, &obj_path, &[])?,
+        Some(fixture_path("inlined_pe.pdb"))
+    );
+
+    let dir =
+        std::env::temp_dir().join(format!("atorsl_test_pdb_file_find_{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+    fs::copy(&obj_path, dir.join("inlined_pe.exe"))?;
+    fs::copy(fixture_path("inlined_macho"), dir.join("inlined_pe.pdb"))?;
+
+    assert_eq!(PdbFile::find(&obj, &dir.join("inlined_pe.exe"), &[])?, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_pdb_file_load_age_mismatch() -> Result<(), Error> {
+    let mut data = fs::read(fixture_path("inlined_pe.exe"))?;
+
+    // The age follows the signature and GUID of the CodeView record.
+    let age = data
+        .windows(4)
+        .position(|window| window == b"RSDS")
+        .expect("image to have a CodeView record")
+        + 20;
+    data[age] = data[age].wrapping_add(1);
+
+    let obj = object::File::parse(&*data)?;
+    assert!(matches!(
+        PdbFile::load(&obj, &fixture_path("inlined_pe.pdb")),
+        Err(Error::PdbMismatch(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_pdb_file_atos() -> Result<(), Error> {
+    let data = fs::read(fixture_path("inlined_pe.exe"))?;
+    let obj = object::File::parse(&*data)?;
+    let pdb_file = PdbFile::load(&obj, &fixture_path("inlined_pe.pdb"))?;
+
+    let symbols = pdb_file.atos(Addr::from(0x140001010), true)?;
+    assert_eq!(
+        symbols
+            .iter()
+            .map(|symbol| (symbol.name.as_str(), symbol.inlined))
+            .collect::<Vec<_>>(),
+        [
+            ("core::num::impl$2::wrapping_mul", true),
+            ("inlined_pe::square", true),
+            ("inlined_pe::sum_of_squares", true),
+            ("inlined_pe::compute", false),
+        ]
+    );
+
+    assert_eq!(
+        symbols[1..]
+            .iter()
+            .map(|symbol| symbol.loc.as_ref().left().map(|loc| loc.line))
+            .collect::<Vec<_>>(),
+        [Some(9), Some(14), Some(20)]
+    );
+
+    let symbols = pdb_file.atos(Addr::from(0x140001010), false)?;
+    assert_eq!(symbols.len(), 1);
+    assert_eq!(symbols[0].name, "inlined_pe::compute");
+
+    let symbols = pdb_file.atos(Addr::from(0x140001020), true)?;
+    assert!(matches!(
+        &symbols[..],
+        [symbol] if symbol.name == "inlined_pe::main"
+            && matches!(&symbol.loc, Either::Left(SourceLoc { line: 25, file, .. })
+                if file.ends_with("inlined_pe.rs"))
+    ));
+
+    assert!(matches!(
+        pdb_file.atos(Addr::from(0x1010), true),
+        Err(Error::AddrNotFound(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_pdb_file_image() -> Result<(), Error> {
+    let data = fs::read(fixture_path("inlined_pe.exe"))?;
+    let obj = object::File::parse(&*data)?;
+    let pdb_file = PdbFile::load(&obj, &fixture_path("inlined_pe.pdb"))?;
+    let cow;
+    let dwarf = load_dwarf!(&obj, cow);
+    let image = Image::new(&obj, Symbolicator::new(&dwarf)?).with_pdb(&pdb_file);
+
+    let symbolications = image
+        .symbolicate(&Loc::Offset, [Addr::from(0x1020)], false)?
+        .collect::<Vec<_>>();
+
+    assert_eq!(symbolications[0].addr, Some(Addr::from(0x140001020)));
+    let frames = symbolications[0].result.as_ref().unwrap();
+    assert_eq!(frames.source, Source::Pdb);
+    assert_eq!(frames.symbols[0].name, "inlined_pe::main");
+
+    Ok(())
+}
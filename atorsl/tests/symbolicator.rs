@@ -0,0 +1,295 @@
+use atorsl::{atos_dwarf, data::Addr, load_dwarf, DebugMap, Error, SplitDwarf, Symbolicator};
+use object::{
+    macho::{MachHeader64, N_OSO},
+    read::macho::MachHeader,
+    Endianness, Object, ObjectSection,
+};
+use std::{fs, path::Path, time::UNIX_EPOCH};
+
+mod common;
+use common::{fixture, fixture_path};
+
+const INLINED_ELF: &str = "inlined_elf";
+const INLINED_SPLIT_ELF: &str = "inlined_split";
+const INLINED_MACHO: &str = "inlined_macho";
+const INLINED_MACHO_ARCHIVE: &str = "inlined_macho_archive";
+
+fn frames(symbolicator: &Symbolicator, addr: u64, include_inlined: bool) -> Vec<String> {
+    symbolicator
+        .atos(Addr::from(addr), include_inlined)
+        .unwrap_or_else(|e| panic!("can't symbolicate: {:#x}\n\terror: {}", addr, e))
+        .into_iter()
+        .map(|symbol| {
+            let loc = symbol
+                .loc
+                .left()
+                .expect("symbol to have a source location");
+            format!(
+                "{} ({}:{})",
+                symbol.name,
+                Path::new(&loc.file)
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy(),
+                loc.line
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_symbolicator_inlined() -> Result<(), Error> {
+    let data = fixture(INLINED_ELF);
+    let obj = object::File::parse(&*data)?;
+    let cow;
+    let dwarf = load_dwarf!(&obj, cow);
+    let symbolicator = Symbolicator::new(&dwarf)?;
+
+    assert_eq!(
+        frames(&symbolicator, 0x401196, true),
+        [
+            "square (inlined.c:5)",
+            "sum_of_squares (inlined.c:7)",
+            "compute (inlined.c:12)",
+        ]
+    );
+
+    assert_eq!(
+        frames(&symbolicator, 0x4011a0, true),
+        ["sum_of_squares (inlined.c:7)", "compute (inlined.c:12)"]
+    );
+
+    assert_eq!(
+        frames(&symbolicator, 0x401196, false),
+        ["compute (inlined.c:5)"]
+    );
+
+    assert_eq!(
+        frames(&symbolicator, 0x401050, true),
+        ["main (inlined.c:16)"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_symbolicator_not_found() -> Result<(), Error> {
+    let data = fixture(INLINED_ELF);
+    let obj = object::File::parse(&*data)?;
+    let cow;
+    let dwarf = load_dwarf!(&obj, cow);
+    let symbolicator = Symbolicator::new(&dwarf)?;
+
+    assert!(matches!(
+        symbolicator.atos(Addr::from(0x401090), true),
+        Err(Error::AddrDebugInfoOffsetMissing(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_symbolicator_without_aranges() -> Result<(), Error> {
+    let data = fixture(INLINED_ELF);
+    let obj = object::File::parse(&*data)?;
+    let dwarf = gimli::Dwarf::load(|section_id| -> Result<_, gimli::Error> {
+        Ok(gimli::EndianSlice::new(
+            obj.section_by_name(section_id.name())
+                .filter(|_| section_id != gimli::SectionId::DebugAranges)
+                .and_then(|section| section.data().ok())
+                .unwrap_or_default(),
+            gimli::RunTimeEndian::Little,
+        ))
+    })?;
+    let symbolicator = Symbolicator::new(&dwarf)?;
+
+    assert_eq!(
+        frames(&symbolicator, 0x401196, true),
+        [
+            "square (inlined.c:5)",
+            "sum_of_squares (inlined.c:7)",
+            "compute (inlined.c:12)",
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_atos_dwarf() -> Result<(), Error> {
+    let data = fixture(INLINED_ELF);
+    let obj = object::File::parse(&*data)?;
+    let cow;
+    let dwarf = load_dwarf!(&obj, cow);
+    let symbolicator = Symbolicator::new(&dwarf)?;
+
+    for addr in [0x401196, 0x4011a0, 0x401050] {
+        assert_eq!(
+            atos_dwarf(&dwarf, Addr::from(addr), true)?,
+            symbolicator.atos(Addr::from(addr), true)?
+        );
+    }
+
+    assert!(matches!(
+        atos_dwarf(&dwarf, Addr::from(0x401090), true),
+        Err(Error::AddrDebugInfoOffsetMissing(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_symbolicator_split_dwarf() -> Result<(), Error> {
+    let data = fixture(INLINED_SPLIT_ELF);
+    let obj = object::File::parse(&*data)?;
+    let cow;
+    let dwarf = load_dwarf!(&obj, cow);
+
+    assert!(matches!(
+        Symbolicator::new(&dwarf)?.atos(Addr::from(0x401196), true),
+        Err(Error::AddrNotFound(_))
+    ));
+
+    let split_dwarf = SplitDwarf::load(&dwarf, &fixture_path(INLINED_SPLIT_ELF), &[])?;
+    let symbolicator = Symbolicator::with_split_dwarf(&dwarf, &split_dwarf)?;
+
+    assert_eq!(
+        frames(&symbolicator, 0x401196, true),
+        [
+            "square (inlined.c:5)",
+            "sum_of_squares (inlined.c:7)",
+            "compute (inlined.c:12)",
+        ]
+    );
+
+    Ok(())
+}
+
+/// Records `mtime` as the modification time of the objects in the `N_OSO` stabs of `data`.
+fn set_object_mtime(data: &mut [u8], mtime: u64) {
+    let header = MachHeader64::<Endianness>::parse(&*data, 0).expect("binary to be a Mach-O");
+    let endian = header.endian().expect("Mach-O to have an endianness");
+    let mut commands = header
+        .load_commands(endian, &*data, 0)
+        .expect("Mach-O to have load commands");
+
+    let mut symtab = None;
+    while let Some(command) = commands.next().expect("load command to be readable") {
+        if let Some(command) = command.symtab().expect("symtab to be readable") {
+            symtab = Some((command.symoff.get(endian), command.nsyms.get(endian)));
+        }
+    }
+
+    let (symoff, nsyms) = symtab.expect("Mach-O to have a symbol table");
+    for index in 0..nsyms {
+        // The n_type and n_value of a 64-bit nlist.
+        let nlist = (symoff + index * 16) as usize;
+        if data[nlist + 4] == N_OSO {
+            data[nlist + 8..nlist + 16].copy_from_slice(&mtime.to_le_bytes());
+        }
+    }
+}
+
+#[test]
+fn test_symbolicator_split_dwarf_compressed() -> Result<(), Error> {
+    let dir = std::env::temp_dir().join(format!("atorsl-split-dwarf-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+
+    let obj_path = dir.join(INLINED_SPLIT_ELF);
+    fs::copy(fixture_path(INLINED_SPLIT_ELF), &obj_path)?;
+    fs::copy(
+        fixture_path("inlined_split_zlib.dwp"),
+        dir.join("inlined_split.dwp"),
+    )?;
+
+    let data = fixture(INLINED_SPLIT_ELF);
+    let obj = object::File::parse(&*data)?;
+    let cow;
+    let dwarf = load_dwarf!(&obj, cow);
+    let split_dwarf = SplitDwarf::load(&dwarf, &obj_path, &[])?;
+    let symbolicator = Symbolicator::with_split_dwarf(&dwarf, &split_dwarf)?;
+
+    assert_eq!(
+        frames(&symbolicator, 0x401196, true),
+        [
+            "square (inlined.c:5)",
+            "sum_of_squares (inlined.c:7)",
+            "compute (inlined.c:12)",
+        ]
+    );
+
+    fs::remove_dir_all(&dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_symbolicator_debug_map_mtime() -> Result<(), Error> {
+    let dir = std::env::temp_dir().join(format!("atorsl-debug-map-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+
+    let object_path = dir.join("inlined_macho.o");
+    fs::copy(fixture_path("inlined_macho.o"), &object_path)?;
+    let object_mtime = fs::metadata(&object_path)?
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .expect("object to be modified after the epoch")
+        .as_secs();
+
+    let obj_path = dir.join(INLINED_MACHO);
+    for (mtime, has_debug_info) in [(object_mtime, true), (object_mtime - 1, false)] {
+        let mut data = fixture(INLINED_MACHO);
+        set_object_mtime(&mut data, mtime);
+        fs::write(&obj_path, &data)?;
+
+        let obj = object::File::parse(&*data)?;
+        let cow;
+        let dwarf = load_dwarf!(&obj, cow);
+        let debug_map = DebugMap::load(&obj, &obj_path)?;
+        let symbolicator = Symbolicator::new(&dwarf)?.with_debug_map(&debug_map);
+
+        assert_eq!(
+            symbolicator
+                .atos(Addr::from(0x1000002d0), true)
+                .is_ok(),
+            has_debug_info
+        );
+    }
+
+    fs::remove_dir_all(&dir).ok();
+    Ok(())
+}
+
+#[test]
+fn test_symbolicator_debug_map() -> Result<(), Error> {
+    for name in [INLINED_MACHO, INLINED_MACHO_ARCHIVE] {
+        let data = fixture(name);
+        let obj = object::File::parse(&*data)?;
+        let cow;
+        let dwarf = load_dwarf!(&obj, cow);
+        let debug_map = DebugMap::load(&obj, &fixture_path(name))?;
+        let symbolicator = Symbolicator::new(&dwarf)?.with_debug_map(&debug_map);
+
+        assert_eq!(
+            frames(&symbolicator, 0x1000002b4, true),
+            [
+                "<i32>::wrapping_mul (int_macros.rs:2173)",
+                "inlined_macho::square::h5a8e55dc13488735 (inlined_macho.rs:12)",
+                "inlined_macho::sum_of_squares::h7a4f856cf6ee070d (inlined_macho.rs:17)",
+                "compute (inlined_macho.rs:23)",
+            ]
+        );
+
+        assert_eq!(
+            frames(&symbolicator, 0x1000002d0, true),
+            ["main (inlined_macho.rs:27)"]
+        );
+
+        assert!(symbolicator
+            .atos(Addr::from(0x1000002b4), false)?
+            .iter()
+            .all(|symbol| symbol.addr == Addr::from(0x1000002b4)));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use atorsl::{data::Addr, load_dwarf, Error, Image, SymCache, Symbolicator};
+
+mod common;
+use common::fixture;
+
+#[test]
+fn test_symcache_atos() -> Result<(), Error> {
+    let data = fixture("inlined_elf");
+    let obj = object::File::parse(&*data)?;
+    let cow;
+    let dwarf = load_dwarf!(&obj, cow);
+
+    let mut symcache_data = Vec::default();
+    SymCache::write(&obj, &Symbolicator::new(&dwarf)?, &mut symcache_data)?;
+    let symcache = SymCache::parse(&symcache_data)?;
+
+    let image = Image::new(&obj, Symbolicator::new(&dwarf)?);
+    for addr in (0x401000..0x401200).map(Addr::from) {
+        for include_inlined in [false, true] {
+            assert_eq!(
+                symcache.atos(addr, include_inlined)?,
+                image.atos(addr, include_inlined)?,
+                "{addr}"
+            );
+        }
+    }
+
+    assert_eq!(
+        SymCache::file_name(&obj)?.as_deref(),
+        Some("a1423422d5e4819d6389557b294103529a6fc66a-x86_64.symcache")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_symcache_invalid() -> Result<(), Error> {
+    let data = fixture("inlined_elf");
+    let obj = object::File::parse(&*data)?;
+    let cow;
+    let dwarf = load_dwarf!(&obj, cow);
+
+    let mut symcache_data = Vec::default();
+    SymCache::write(&obj, &Symbolicator::new(&dwarf)?, &mut symcache_data)?;
+
+    symcache_data[8] += 1;
+    assert!(matches!(
+        SymCache::parse(&symcache_data),
+        Err(Error::SymCacheVersion(_))
+    ));
+
+    assert!(matches!(
+        SymCache::parse(&data),
+        Err(Error::SymCacheInvalid)
+    ));
+
+    // The inlined functions of the first function, past the end of the cache.
+    symcache_data[8] -= 1;
+    symcache_data[76..80].copy_from_slice(&u32::MAX.to_le_bytes());
+    let symcache = SymCache::parse(&symcache_data)?;
+    let addr = Addr::from(u64::from_le_bytes(
+        symcache_data[40..48].try_into().unwrap(),
+    ));
+
+    assert!(matches!(symcache.check(), Err(Error::SymCacheInvalid)));
+    assert!(matches!(
+        symcache.atos(addr, true),
+        Err(Error::SymCacheInvalid)
+    ));
+
+    Ok(())
+}
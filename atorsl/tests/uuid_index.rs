@@ -0,0 +1,52 @@
+use atorsl::{data::Arch, Error, UuidIndex};
+use std::path::PathBuf;
+
+mod common;
+use common::fixtures_path;
+
+const INLINED_MACHO_UUID: [u8; 16] = [
+    0x4c, 0x4c, 0x44, 0xca, 0x55, 0x55, 0x31, 0x44, 0xa1, 0x47, 0x1f, 0xd6, 0x51, 0x4c, 0x28, 0x82,
+];
+
+#[test]
+fn test_uuid_index() -> Result<(), Error> {
+    let index = UuidIndex::index(&[fixtures_path("")])?;
+    let images = index.find_all(&INLINED_MACHO_UUID);
+
+    assert_eq!(
+        images
+            .iter()
+            .map(|image| (
+                image.path.strip_prefix(fixtures_path("")).unwrap(),
+                image.arch,
+                image.has_dwarf
+            ))
+            .collect::<Vec<_>>(),
+        [
+            (
+                PathBuf::from("objects/inlined_macho.dSYM/Contents/Resources/DWARF/inlined_macho")
+                    .as_path(),
+                Arch::from_name("x86_64"),
+                true
+            ),
+            (
+                PathBuf::from(
+                    "objects/inlined_macho.framework.dSYM/Contents/Resources/DWARF/inlined_macho"
+                )
+                .as_path(),
+                Arch::from_name("x86_64"),
+                true
+            ),
+            (
+                PathBuf::from("objects/inlined_macho").as_path(),
+                Arch::from_name("x86_64"),
+                false
+            ),
+        ]
+    );
+
+    assert_eq!(index.find(&INLINED_MACHO_UUID), images.first());
+    assert_eq!(index.find(&[0; 16]), None);
+
+    Ok(())
+}
@@ -0,0 +1,19 @@
+// gcc -g -O2 -fno-pie -no-pie -fdebug-prefix-map="$PWD"=/src -o inlined_elf inlined.c && ators dump-syms inlined_elf > inlined_elf.sym
+#include <stdio.h>
+#include <stdlib.h>
+
+static inline int square(int x) { return x * x; }
+
+static inline int sum_of_squares(int a, int b) { return square(a) + square(b); }
+
+__attribute__((noinline)) int compute(int n) {
+    int sum = 0;
+    for (int i = 0; i < n; i++)
+        sum += sum_of_squares(i, n - i);
+    return sum;
+}
+
+int main(int argc, char **argv) {
+    printf("%d\n", compute(argc > 1 ? atoi(argv[1]) : 0));
+    return 0;
+}
@@ -0,0 +1,34 @@
+// rustc --target x86_64-apple-darwin -g -C opt-level=2 -C panic=abort --crate-type=lib \
+//     --remap-path-prefix="$PWD"=/src --emit=obj -o inlined_macho.o inlined_macho.rs
+// llvm-ar rcs libinlined_macho.a inlined_macho.o
+// ld64.lld -arch x86_64 -platform_version macos 11.0 11.0 -e _main -oso_prefix "$PWD/" \
+//     -o inlined_macho inlined_macho.o
+// ld64.lld -arch x86_64 -platform_version macos 11.0 11.0 -e _main -oso_prefix "$PWD/" \
+//     -o inlined_macho_archive libinlined_macho.a
+#![no_std]
+
+#[inline(always)]
+fn square(x: i32) -> i32 {
+    x.wrapping_mul(x)
+}
+
+#[inline(always)]
+fn sum_of_squares(a: i32, b: i32) -> i32 {
+    square(a).wrapping_add(square(b))
+}
+
+#[no_mangle]
+#[inline(never)]
+pub extern "C" fn compute(a: i32, b: i32) -> i32 {
+    sum_of_squares(a, b).wrapping_mul(3)
+}
+
+#[no_mangle]
+pub extern "C" fn main() -> i32 {
+    compute(2, 3)
+}
+
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
@@ -0,0 +1,31 @@
+// rustc --target x86_64-pc-windows-msvc -g -C opt-level=2 -C panic=abort --crate-type=lib \
+//     --remap-path-prefix="$PWD"=/src --emit=obj -o inlined_pe.o inlined_pe.rs
+// lld-link /entry:main /subsystem:console /nodefaultlib /debug /pdbaltpath:%_PDB% /brepro \
+//     /pdbsourcepath:/src /out:inlined_pe.exe inlined_pe.o
+#![no_std]
+
+#[inline(always)]
+fn square(x: i32) -> i32 {
+    x.wrapping_mul(x)
+}
+
+#[inline(always)]
+fn sum_of_squares(a: i32, b: i32) -> i32 {
+    square(a).wrapping_add(square(b))
+}
+
+#[no_mangle]
+#[inline(never)]
+pub extern "C" fn compute(a: i32, b: i32) -> i32 {
+    sum_of_squares(a, b).wrapping_mul(3)
+}
+
+#[no_mangle]
+pub extern "C" fn main() -> i32 {
+    compute(2, 3)
+}
+
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}